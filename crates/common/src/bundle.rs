@@ -0,0 +1,198 @@
+use hdk::prelude::*;
+use std::collections::HashMap;
+
+use rep_lang_core::abstract_syntax::Expr;
+use rep_lang_runtime::{eval::FlatValue, types::Scheme};
+
+use crate::{
+    create_sensemaker_entry_full, error::SensemakerError, get_sensemaker_entry_by_headerhash,
+    BatchOperand, CreateSensemakerEntryInput, Marker, SensemakerEntry, SensemakerOperand,
+};
+
+/// the first line of a bundle in CLI format.
+pub const BUNDLE_CLI_HEADER: &str = "sensemaker-bundle v1";
+
+/// a `SensemakerEntry`, as carried by a `SensemakerBundle`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleNode {
+    /// the `HeaderHash` of the entry in the network it was exported from.
+    pub origin_hh: HeaderHash,
+    pub operator: Expr,
+    /// `BatchOperand::Index` refers to an earlier node of the bundle.
+    /// `BatchOperand::Existing` is an `OtherOperand`, which must already exist on the
+    /// importing network.
+    pub operands: Vec<BatchOperand>,
+    pub output_scheme: Scheme,
+    pub output_flat_value: FlatValue<Marker>,
+}
+
+/// a set of `SensemakerEntry`s, closed over their `SensemakerOperand`s, in topological
+/// order (every node comes after its operands).
+#[derive(Debug, Clone, Serialize, Deserialize, SerializedBytes)]
+pub struct SensemakerBundle {
+    pub nodes: Vec<BundleNode>,
+}
+
+impl SensemakerBundle {
+    /// encode the bundle as text: `BUNDLE_CLI_HEADER`, then the base64 of its msgpack
+    /// encoding on a single line.
+    pub fn to_cli_format(&self) -> Result<String, SensemakerError> {
+        let bytes =
+            holochain_serialized_bytes::encode(self).map_err(|err| SensemakerError::Other {
+                message: format!("could not encode bundle: {:?}", err),
+            })?;
+        Ok(format!(
+            "{}\n{}\n",
+            BUNDLE_CLI_HEADER,
+            base64::encode(bytes)
+        ))
+    }
+
+    pub fn from_cli_format(s: &str) -> Result<Self, SensemakerError> {
+        let invalid = |message: String| SensemakerError::InvalidInput { message };
+        let mut lines = s.lines().map(str::trim).filter(|l| !l.is_empty());
+        match lines.next() {
+            Some(BUNDLE_CLI_HEADER) => {}
+            other => {
+                return Err(invalid(format!(
+                    "expected bundle header {:?}, found {:?}",
+                    BUNDLE_CLI_HEADER, other
+                )))
+            }
+        }
+        let body = lines.next().ok_or_else(|| invalid("empty bundle".into()))?;
+        let bytes =
+            base64::decode(body).map_err(|err| invalid(format!("bundle base64: {}", err)))?;
+        holochain_serialized_bytes::decode(&bytes)
+            .map_err(|err| invalid(format!("bundle msgpack: {:?}", err)))
+    }
+}
+
+/// collect the `SensemakerEntry`s at `roots`, and all `SensemakerEntry`s they depend
+/// upon, into a `SensemakerBundle`.
+#[hdk_extern]
+pub fn export_sensemaker_bundle(roots: Vec<HeaderHash>) -> ExternResult<SensemakerBundle> {
+    let mut bundle = SensemakerBundle { nodes: Vec::new() };
+    let mut indices: HashMap<HeaderHash, usize> = HashMap::new();
+    for hh in roots {
+        export_node(hh, &mut bundle, &mut indices)?;
+    }
+    Ok(bundle)
+}
+
+/// an entry whose operands are being exported, `operands.len()` of them so far.
+struct ExportFrame {
+    hh: HeaderHash,
+    se: SensemakerEntry,
+    operands: Vec<BatchOperand>,
+}
+
+impl ExportFrame {
+    fn new(hh: HeaderHash) -> ExternResult<Self> {
+        let (_eh, se) = get_sensemaker_entry_by_headerhash(hh.clone())?;
+        Ok(ExportFrame {
+            hh,
+            se,
+            operands: Vec::new(),
+        })
+    }
+}
+
+/// post-order traversal: operands are pushed before the entry which consumes them. the
+/// traversal keeps an explicit stack, so a long chain of operands cannot overflow ours.
+fn export_node(
+    hh: HeaderHash,
+    bundle: &mut SensemakerBundle,
+    indices: &mut HashMap<HeaderHash, usize>,
+) -> ExternResult<()> {
+    if indices.contains_key(&hh) {
+        return Ok(());
+    }
+    let mut stack: Vec<ExportFrame> = vec![ExportFrame::new(hh)?];
+    while let Some(frame) = stack.last_mut() {
+        match frame.se.operands.get(frame.operands.len()).cloned() {
+            Some(SensemakerOperand::SensemakerOperand(operand_hh)) => {
+                match indices.get(&operand_hh) {
+                    Some(idx) => frame.operands.push(BatchOperand::Index(*idx)),
+                    // the operand is exported first, then we return to this frame.
+                    None => stack.push(ExportFrame::new(operand_hh)?),
+                }
+            }
+            Some(other @ SensemakerOperand::OtherOperand(_)) => {
+                frame.operands.push(BatchOperand::Existing(other))
+            }
+            None => {
+                if let Some(ExportFrame { hh, se, operands }) = stack.pop() {
+                    bundle.nodes.push(BundleNode {
+                        origin_hh: hh.clone(),
+                        operator: se.operator,
+                        operands,
+                        output_scheme: se.output_scheme,
+                        output_flat_value: se.output_flat_value,
+                    });
+                    indices.insert(hh, bundle.nodes.len() - 1);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// re-create the nodes of `bundle`, returning their new `HeaderHash`es in bundle
+/// order.
+///
+/// every node is re-evaluated, & must reproduce the `Scheme` & value it was exported
+/// with. should any node fail, the zome call fails & nothing is committed.
+#[hdk_extern]
+pub fn import_sensemaker_bundle(bundle: SensemakerBundle) -> ExternResult<Vec<HeaderHash>> {
+    let mut hh_s: Vec<HeaderHash> = Vec::new();
+    for (idx, node) in bundle.nodes.into_iter().enumerate() {
+        let hh = import_node(node, &hh_s).map_err(|err| SensemakerError::BatchItem {
+            index: idx,
+            error: Box::new(err),
+        })?;
+        hh_s.push(hh);
+    }
+    Ok(hh_s)
+}
+
+fn import_node(node: BundleNode, hh_s: &[HeaderHash]) -> Result<HeaderHash, SensemakerError> {
+    let mut args = Vec::new();
+    for operand in node.operands {
+        let arg = match operand {
+            BatchOperand::Existing(operand) => operand,
+            BatchOperand::Index(arg_idx) => match hh_s.get(arg_idx) {
+                Some(hh) => SensemakerOperand::SensemakerOperand(hh.clone()),
+                None => {
+                    return Err(SensemakerError::InvalidInput {
+                        message: format!(
+                            "operand refers to node {}, which does not precede it",
+                            arg_idx
+                        ),
+                    })
+                }
+            },
+        };
+        args.push(arg);
+    }
+    let (hh, _eh, se) = create_sensemaker_entry_full(CreateSensemakerEntryInput {
+        expr: node.operator,
+        args,
+        expected_scheme: None,
+    })?;
+    if se.output_scheme != node.output_scheme {
+        return Err(SensemakerError::SchemeMismatch {
+            expected: node.output_scheme,
+            actual: se.output_scheme,
+        });
+    }
+    if se.output_flat_value != node.output_flat_value {
+        return Err(SensemakerError::InvalidInput {
+            message: format!(
+                "node {} re-evaluated to a different value:\nexported: {:?}\nimported: {:?}",
+                node.origin_hh, node.output_flat_value, se.output_flat_value
+            ),
+        });
+    }
+    Ok(hh)
+}
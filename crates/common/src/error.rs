@@ -0,0 +1,128 @@
+use hdk::prelude::*;
+use std::fmt;
+
+use rep_lang_core::abstract_syntax::Gas;
+use rep_lang_runtime::types::Scheme;
+
+/// the errors raised by the sensemaker API.
+///
+/// externs must return `ExternResult`, so a `SensemakerError` crosses the zome boundary
+/// as a `WasmError::Guest` holding its JSON encoding (tagged by `kind`). callers can
+/// recover it with `SensemakerError::from_wasm_error`, or `JSON.parse` it in JS.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum SensemakerError {
+    /// a rep_lang expression failed to parse.
+    Parse { input: String, message: String },
+    /// an operand string, as passed to `create_sensemaker_entry_parse`, is malformed.
+    OperandParse {
+        arg_idx: usize,
+        arg: String,
+        message: String,
+    },
+    /// type inference failed. `context` names what was being inferred.
+    Type { context: String, message: String },
+    /// a `Scheme` did not unify with the one required of it.
+    SchemeMismatch { expected: Scheme, actual: Scheme },
+    /// nothing could be retrieved at `hash`.
+    Dereference { hash: String },
+    /// something was retrieved at `hash`, but it was not the expected kind of entry.
+    NonPresent { hash: String, expected: String },
+    /// evaluation consumed more gas than the DNA's `max_gas` allows.
+    GasBudgetExceeded { used: Gas, max_gas: Gas },
+    /// an entry exceeds one of the DNA's size limits, named by `limit`.
+    LimitExceeded {
+        limit: String,
+        value: usize,
+        max: usize,
+    },
+    /// a state machine path lacks the entry linked under `tag`.
+    StateMachine { path: String, tag: String },
+    /// the input to an extern is malformed in some other way.
+    InvalidInput { message: String },
+    /// the `index`th item of a batch failed.
+    BatchItem {
+        index: usize,
+        error: Box<SensemakerError>,
+    },
+    /// any other failure, including those of the host.
+    Other { message: String },
+}
+
+impl SensemakerError {
+    /// recover a `SensemakerError` from the `WasmError` it was converted into. any other
+    /// `WasmError` yields `None`.
+    pub fn from_wasm_error(err: &WasmError) -> Option<Self> {
+        match err {
+            WasmError::Guest(msg) => serde_json::from_str(msg).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SensemakerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SensemakerError::Parse { input, message } => {
+                write!(f, "parse error in {:?}:\n\n{}\n", input, message)
+            }
+            SensemakerError::OperandParse {
+                arg_idx,
+                arg,
+                message,
+            } => write!(f, "arg {}: {:?}: {}", arg_idx, arg, message),
+            SensemakerError::Type { context, message } => {
+                write!(f, "type error in {}: {}", context, message)
+            }
+            SensemakerError::SchemeMismatch { expected, actual } => write!(
+                f,
+                "scheme mismatch:\nexpected: {:?}\nactual: {:?}",
+                expected, actual
+            ),
+            SensemakerError::Dereference { hash } => write!(f, "could not dereference: {}", hash),
+            SensemakerError::NonPresent { hash, expected } => {
+                write!(f, "no {} present at: {}", expected, hash)
+            }
+            SensemakerError::GasBudgetExceeded { used, max_gas } => write!(
+                f,
+                "gas budget exceeded: evaluation used {} gas, but max_gas is {}",
+                used, max_gas
+            ),
+            SensemakerError::LimitExceeded { limit, value, max } => {
+                write!(
+                    f,
+                    "limit exceeded: {} is {}, but may be at most {}",
+                    limit, value, max
+                )
+            }
+            SensemakerError::StateMachine { path, tag } => {
+                write!(f, "state machine: no `{}` at path {:?}", tag, path)
+            }
+            SensemakerError::InvalidInput { message } => write!(f, "invalid input: {}", message),
+            SensemakerError::BatchItem { index, error } => {
+                write!(f, "batch item {}: {}", index, error)
+            }
+            SensemakerError::Other { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<SensemakerError> for WasmError {
+    fn from(err: SensemakerError) -> Self {
+        match serde_json::to_string(&err) {
+            Ok(json) => WasmError::Guest(json),
+            Err(_) => WasmError::Guest(err.to_string()),
+        }
+    }
+}
+
+impl From<WasmError> for SensemakerError {
+    fn from(err: WasmError) -> Self {
+        match SensemakerError::from_wasm_error(&err) {
+            Some(sensemaker_err) => sensemaker_err,
+            None => SensemakerError::Other {
+                message: format!("{:?}", err),
+            },
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use hdk::prelude::*;
+
+use rep_lang_core::abstract_syntax::{Expr, Gas, Name};
+use rep_lang_runtime::{eval::FlatValue, types::Scheme};
+
+use crate::{
+    error::SensemakerError, eval_application_traced, get_sensemaker_entry_by_headerhash,
+    resolve_operand, Marker, SensemakerOperand,
+};
+
+/// the fresh name which an operand's value was bound to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExplainBinding {
+    pub name: Name,
+    pub operand: SensemakerOperand,
+    pub scheme: Scheme,
+    pub flat_value: FlatValue<Marker>,
+}
+
+/// the gas spent in each phase of the evaluation. `eval_` does not expose its individual
+/// reductions, so the phases are as fine-grained as an explanation can be.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExplainPhases {
+    /// normalizing the operator & operands, and binding the latter.
+    pub setup_gas: Gas,
+    /// evaluating `full_application`.
+    pub eval_gas: Gas,
+    /// forcing the (lazily evaluated) result to a `FlatValue`.
+    pub force_gas: Gas,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SensemakerEntryExplanation {
+    pub bindings: Vec<ExplainBinding>,
+    /// the normalized operator, in terms of which `full_application` is written.
+    pub operator: Expr,
+    /// the operator, applied to each of the `ExplainBinding` names.
+    pub full_application: Expr,
+    pub phases: ExplainPhases,
+    pub output_scheme: Scheme,
+    pub output_flat_value: FlatValue<Marker>,
+    /// the sum of the phases' gas, which is the entry's `start_gas`.
+    pub total_gas: Gas,
+}
+
+/// re-runs the evaluation of the `SensemakerEntry` at `se_hh`, recording the bindings &
+/// the gas spent in each phase.
+#[hdk_extern]
+pub fn explain_sensemaker_entry(se_hh: HeaderHash) -> ExternResult<SensemakerEntryExplanation> {
+    let (_se_eh, se) = get_sensemaker_entry_by_headerhash(se_hh.clone())?;
+
+    let arg_scheme_values = se
+        .operands
+        .iter()
+        .map(resolve_operand)
+        .collect::<ExternResult<_>>()?;
+    let (output_scheme, output_flat_value, total_gas, trace) =
+        eval_application_traced(&se.operator, arg_scheme_values, Some(se.start_gas))?;
+    // validation recomputes `start_gas` in just the same way, so this is only possible
+    // if the entry (or one of its operands) could not be retrieved intact.
+    if total_gas != se.start_gas {
+        return Err(SensemakerError::Other {
+            message: format!(
+                "{} re-evaluated with {} gas, but recorded {}",
+                se_hh, total_gas, se.start_gas
+            ),
+        }
+        .into());
+    }
+
+    let bindings = trace
+        .bindings
+        .into_iter()
+        .zip(se.operands)
+        .map(|((name, scheme, flat_value), operand)| ExplainBinding {
+            name,
+            operand,
+            scheme,
+            flat_value,
+        })
+        .collect();
+    let phases = ExplainPhases {
+        setup_gas: trace.setup_gas,
+        eval_gas: trace.eval_gas,
+        force_gas: trace.force_gas,
+    };
+
+    Ok(SensemakerEntryExplanation {
+        bindings,
+        operator: trace.operator,
+        full_application: trace.full_application,
+        phases,
+        output_scheme,
+        output_flat_value,
+        total_gas,
+    })
+}
@@ -2,32 +2,48 @@
 
 use hdk::{
     entry::must_get_valid_element,
-    prelude::{holo_hash::DnaHash, *},
+    prelude::{
+        holo_hash::{DnaHash, HeaderHashB64},
+        *,
+    },
 };
 
 use combine::{stream::position, EasyParser, StreamOnce};
 use pretty::RcDoc;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use rep_lang_concrete_syntax::{parse::expr, pretty::ppr_expr};
 use rep_lang_core::{
-    abstract_syntax::{Expr, Gas, Name, PrimOp},
+    abstract_syntax::{Expr, Gas, Lit, Name, PrimOp},
     app, lam,
 };
 use rep_lang_runtime::{
     env::Env,
     eval::{
         eval_, flat_thunk_to_sto_ref, inject_flatvalue_to_flatthunk, lookup_sto, new_term_env,
-        value_to_flat_value, EvalState, FlatValue, Normalizable, Sto,
+        value_to_flat_value, EvalState, FlatValue, Normalizable, Sto, Value,
     },
     infer::{self, infer_expr_with_is, normalize, unifies, InferState},
-    types::Scheme,
+    types::{Scheme, Type},
+};
+use social_sensemaker_core::{
+    DEPENDENT_TAG, OWNER_TAG, SCHEME_BUCKET_TAG, SM_COMP_TAG, SM_DATA_TAG, SM_INIT_TAG,
+    SM_SNAPSHOT_TAG,
 };
-use social_sensemaker_core::{OWNER_TAG, SM_DATA_TAG, SM_INIT_TAG};
 use social_sensemaker_macros::expand_remote_calls;
 
+pub mod bundle;
+pub mod error;
+pub mod explain;
+pub mod properties;
 pub mod util;
 
+use error::SensemakerError;
+use properties::{
+    flat_value_limit_violation, input_limit_violation, other_operand_limit_violation,
+    sensemaker_properties,
+};
+
 // TODO think carefully on what this should be.
 pub type Marker = ();
 
@@ -35,11 +51,19 @@ pub type Marker = ();
 pub enum SensemakerOperand {
     // these dereference to `SensemakerEntry`
     SensemakerOperand(HeaderHash),
-    // these dereference to `FlatThunk`??
+    // these dereference to any other app entry, whose serialized bytes are
+    // exposed as a `List Int`. see `bytes_to_scheme_flat_value`.
     OtherOperand(HeaderHash),
 }
 
 impl SensemakerOperand {
+    pub fn header_hash(&self) -> &HeaderHash {
+        match &self {
+            SensemakerOperand::SensemakerOperand(hh) => hh,
+            SensemakerOperand::OtherOperand(hh) => hh,
+        }
+    }
+
     pub fn ppr(&self) -> RcDoc<()> {
         match &self {
             SensemakerOperand::SensemakerOperand(hh) => {
@@ -130,6 +154,9 @@ impl SensemakerEntry {
 pub struct CreateSensemakerEntryInput {
     pub expr: Expr,
     pub args: Vec<SensemakerOperand>,
+    /// if present, the entry is rejected unless its `output_scheme` unifies with this.
+    #[serde(default)]
+    pub expected_scheme: Option<Scheme>,
 }
 
 #[hdk_entry]
@@ -140,13 +167,159 @@ pub struct SchemeEntry {
     pub sc: Scheme,
 }
 
+/// an intermediate node of the Scheme index, between the `SchemeRoot` & the
+/// `SchemeEntry`s. see `scheme_bucket_key`.
+#[hdk_entry]
+pub struct SchemeBucket {
+    pub key: String,
+}
+
+/// the bucket key of `SchemeEntry`s whose `Type` is a bare type variable. such
+/// `Scheme`s unify with anything, so this bucket is visited by every lookup.
+pub const TVAR_BUCKET_KEY: &str = "TVar";
+
+/// `SchemeEntry`s are bucketed by the outermost constructor of their `Type`, e.g. all
+/// `TArr`s together & each `TCon` by name. two `Type`s whose outermost constructors
+/// differ cannot unify, unless one of them is a type variable.
+///
+/// we deliberately do not bucket `TArr`s by arity: a type variable in return position
+/// may be instantiated to another arrow.
+///
+/// the keys are stored on the DHT & checked by validation, so they must never change.
+/// hence they are spelled out here, rather than derived from rep_lang's `Debug` output.
+pub fn scheme_bucket_key(sc: &Scheme) -> String {
+    let Scheme(_, ty) = sc;
+    match ty {
+        Type::TVar(_) => TVAR_BUCKET_KEY.into(),
+        Type::TCon(con) => format!("TCon \"{}\"", con),
+        Type::TArr(_, _) => "TArr".into(),
+        Type::TList(_) => "TList".into(),
+        Type::TPair(_, _) => "TPair".into(),
+    }
+}
+
 // functions
 
 #[hdk_extern]
 pub fn get_sensemaker_entries_which_unify(
     opt_target_sc: Option<Scheme>,
 ) -> ExternResult<Vec<(HeaderHash, SensemakerEntry)>> {
-    get_linked_sensemaker_entries_which_unify((hash_entry(SchemeRoot)?, opt_target_sc))
+    let scheme_entry_hashes = bucketed_scheme_entry_hashes(&opt_target_sc)?;
+    let filtered_scheme_entry_hashes =
+        filter_unifying_scheme_entry_hashes(scheme_entry_hashes, opt_target_sc);
+    scheme_entries_linked_sensemaker_entries(filtered_scheme_entry_hashes)
+}
+
+/// a position in the ordering of `get_sensemaker_entries_which_unify_paginated`, which
+/// orders entries by the timestamp of their Scheme index link.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnifyCursor {
+    pub timestamp: Timestamp,
+    // tie-breaker for links with equal timestamps
+    pub create_link_hash: HeaderHash,
+}
+
+/// input to `get_sensemaker_entries_which_unify_paginated`
+#[derive(Debug, Serialize, Deserialize, SerializedBytes)]
+pub struct UnifyPageInput {
+    pub opt_target_sc: Option<Scheme>,
+    /// resume after this position. `None` starts from the first page.
+    pub cursor: Option<UnifyCursor>,
+    pub page_size: usize,
+    /// whether to also count the matching entries across all pages. deleted entries are
+    /// not counted, so this fetches every matching entry.
+    pub include_total: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SensemakerEntriesPage {
+    pub entries: Vec<(HeaderHash, SensemakerEntry)>,
+    /// `None` once there are no further pages.
+    pub next_cursor: Option<UnifyCursor>,
+    pub total: Option<usize>,
+}
+
+/// a paginated `get_sensemaker_entries_which_unify`. only the `SensemakerEntry`s on the
+/// requested page are fetched.
+#[hdk_extern]
+pub fn get_sensemaker_entries_which_unify_paginated(
+    input: UnifyPageInput,
+) -> ExternResult<SensemakerEntriesPage> {
+    if input.page_size == 0 {
+        return Err(SensemakerError::InvalidInput {
+            message: "page_size must be positive".into(),
+        }
+        .into());
+    }
+
+    let scheme_entry_hashes = bucketed_scheme_entry_hashes(&input.opt_target_sc)?;
+    let filtered_scheme_entry_hashes =
+        filter_unifying_scheme_entry_hashes(scheme_entry_hashes, input.opt_target_sc);
+    let mut links: Vec<Link> = Vec::new();
+    for s_eh in filtered_scheme_entry_hashes {
+        links.extend(get_links_exact(s_eh, OWNER_TAG)?);
+    }
+    links.sort_by(|x, y| {
+        (x.timestamp, &x.create_link_hash).cmp(&(y.timestamp, &y.create_link_hash))
+    });
+
+    let total = if input.include_total {
+        let mut num_live = 0;
+        for lnk in &links {
+            if let Some(se_eh) = lnk.target.clone().into_entry_hash() {
+                if get_live_sensemaker_entry(se_eh)?.is_some() {
+                    num_live += 1;
+                }
+            }
+        }
+        Some(num_live)
+    } else {
+        None
+    };
+    let remaining: Vec<Link> = match input.cursor {
+        None => links,
+        Some(cursor) => links
+            .into_iter()
+            .filter(|lnk| {
+                (lnk.timestamp, &lnk.create_link_hash)
+                    > (cursor.timestamp, &cursor.create_link_hash)
+            })
+            .collect(),
+    };
+    let has_more = remaining.len() > input.page_size;
+    let page: Vec<Link> = remaining.into_iter().take(input.page_size).collect();
+    let next_cursor = if has_more {
+        page.last().map(|lnk| UnifyCursor {
+            timestamp: lnk.timestamp,
+            create_link_hash: lnk.create_link_hash.clone(),
+        })
+    } else {
+        None
+    };
+    // deleted entries are skipped, so a page may hold fewer than `page_size` entries.
+    let entries = page
+        .into_iter()
+        .filter_map(|lnk| lnk.target.into_entry_hash())
+        .map(get_live_sensemaker_entry)
+        .filter_map(Result::transpose)
+        .collect::<ExternResult<_>>()?;
+
+    Ok(SensemakerEntriesPage {
+        entries,
+        next_cursor,
+        total,
+    })
+}
+
+/// the links from `base` tagged exactly `tag`. `get_links` matches tags by prefix, so
+/// would also return e.g. links tagged `"sensemaker_owner_x"` for `OWNER_TAG`.
+pub fn get_links_exact(base: impl Into<AnyLinkableHash>, tag: &str) -> ExternResult<Vec<Link>> {
+    let link_tag = LinkTag::new(tag);
+    let links = get_links(base, Some(link_tag.clone()))?;
+    Ok(links
+        .into_iter()
+        .filter(|lnk| lnk.tag == link_tag)
+        .collect())
 }
 
 // this doesn't really make sense, because the only structure which is guaranteed to have
@@ -155,16 +328,62 @@ pub fn get_sensemaker_entries_which_unify(
 pub fn get_linked_sensemaker_entries_which_unify(
     (target_hash, opt_target_sc): (EntryHash, Option<Scheme>),
 ) -> ExternResult<Vec<(HeaderHash, SensemakerEntry)>> {
-    let scheme_entry_links = get_links(target_hash, None)?;
-    let scheme_entry_hashes: Vec<EntryHash> = scheme_entry_links
+    if target_hash == hash_entry(SchemeRoot)? {
+        return get_sensemaker_entries_which_unify(opt_target_sc);
+    }
+    let scheme_entry_hashes: Vec<EntryHash> = get_links_exact(target_hash, OWNER_TAG)?
         .into_iter()
-        .map(|lnk| {
-            lnk.target
-                .into_entry_hash()
-                .expect("Link target should be Entry.")
-        })
+        .filter_map(|lnk| lnk.target.into_entry_hash())
         .collect();
-    let filtered_scheme_entry_hashes: Vec<EntryHash> = match opt_target_sc {
+    let filtered_scheme_entry_hashes =
+        filter_unifying_scheme_entry_hashes(scheme_entry_hashes, opt_target_sc);
+    scheme_entries_linked_sensemaker_entries(filtered_scheme_entry_hashes)
+}
+
+/// collect the `SchemeEntry` hashes from every bucket which could hold a `Scheme`
+/// unifying with `opt_target_sc`.
+fn bucketed_scheme_entry_hashes(opt_target_sc: &Option<Scheme>) -> ExternResult<Vec<EntryHash>> {
+    let scheme_root_hash = hash_entry(SchemeRoot)?;
+    let bucket_hashes: Vec<EntryHash> = match opt_target_sc {
+        Some(target_sc) if scheme_bucket_key(target_sc) != TVAR_BUCKET_KEY => vec![
+            hash_entry(SchemeBucket {
+                key: scheme_bucket_key(target_sc),
+            })?,
+            hash_entry(SchemeBucket {
+                key: TVAR_BUCKET_KEY.into(),
+            })?,
+        ],
+        // no target, or a type variable target: every bucket is compatible
+        _ => get_links_exact(scheme_root_hash.clone(), SCHEME_BUCKET_TAG)?
+            .into_iter()
+            .filter_map(|lnk| lnk.target.into_entry_hash())
+            .collect(),
+    };
+
+    // `SchemeEntry`s created before the index was bucketed hang directly off of the
+    // `SchemeRoot`. validation rejects new links of that form, so this set is fixed.
+    let legacy_links = get_links_exact(scheme_root_hash, OWNER_TAG)?;
+
+    let mut bucketed_links = Vec::new();
+    for bucket_hash in bucket_hashes {
+        bucketed_links.extend(get_links_exact(bucket_hash, OWNER_TAG)?);
+    }
+
+    let mut seen: HashSet<EntryHash> = HashSet::new();
+    let scheme_entry_hashes = bucketed_links
+        .into_iter()
+        .chain(legacy_links)
+        .filter_map(|lnk| lnk.target.into_entry_hash())
+        .filter(|s_eh| seen.insert(s_eh.clone()))
+        .collect();
+    Ok(scheme_entry_hashes)
+}
+
+fn filter_unifying_scheme_entry_hashes(
+    scheme_entry_hashes: Vec<EntryHash>,
+    opt_target_sc: Option<Scheme>,
+) -> Vec<EntryHash> {
+    match opt_target_sc {
         // if no target Scheme, we do not filter
         None => scheme_entry_hashes,
         // if yes target Scheme, we filter based on unification
@@ -176,20 +395,8 @@ pub fn get_linked_sensemaker_entries_which_unify(
                 .filter(|s_eh| {
                     let mut flag = || -> ExternResult<bool> {
                         // retrieve `SchemeEntry` element, decode to entry
-                        let element = (match get(s_eh.clone(), GetOptions::content())? {
-                            Some(el) => Ok(el),
-                            None => Err(WasmError::Guest(format!(
-                                "could not dereference hash: {}",
-                                s_eh.clone()
-                            ))),
-                        })?;
                         let scheme_entry: SchemeEntry =
-                            match element.into_inner().1.to_app_option()? {
-                                Some(se) => Ok(se),
-                                None => {
-                                    Err(WasmError::Guest(format!("non-present arg: {}", *s_eh)))
-                                }
-                            }?;
+                            util::try_get_and_convert(s_eh.clone(), GetOptions::content())?;
                         // check unification of normalized type
                         let Scheme(_, normalized_candidate_ty) =
                             normalize(&mut is, scheme_entry.sc);
@@ -202,21 +409,36 @@ pub fn get_linked_sensemaker_entries_which_unify(
                 })
                 .collect()
         }
-    };
-    filtered_scheme_entry_hashes
+    }
+}
+
+fn scheme_entries_linked_sensemaker_entries(
+    scheme_entry_hashes: Vec<EntryHash>,
+) -> ExternResult<Vec<(HeaderHash, SensemakerEntry)>> {
+    scheme_entry_hashes
         .into_iter()
-        .flat_map(|s_eh| get_links(s_eh, None))
+        .flat_map(|s_eh| get_links_exact(s_eh, OWNER_TAG))
         .flatten()
-        .map(|lnk| {
-            get_sensemaker_entry(
-                lnk.target
-                    .into_entry_hash()
-                    .expect("Link target should be Entry."),
-            )
-        })
+        .filter_map(|lnk| lnk.target.into_entry_hash())
+        .map(get_live_sensemaker_entry)
+        .filter_map(Result::transpose)
         .collect()
 }
 
+/// like `get_sensemaker_entry`, but yields `None` for an entry which has been deleted.
+fn get_live_sensemaker_entry(
+    se_eh: EntryHash,
+) -> ExternResult<Option<(HeaderHash, SensemakerEntry)>> {
+    match get(se_eh, GetOptions::content())? {
+        None => Ok(None),
+        Some(element) => {
+            let hh = element.header_address().clone();
+            let se = util::try_from_element(element)?;
+            Ok(Some((hh, se)))
+        }
+    }
+}
+
 /// this function creates an `SensemakerEntry`, whose `Scheme` is essentially
 /// `forall a. List a`.
 ///
@@ -242,7 +464,7 @@ pub fn pack_ses_into_list_se(ses: Vec<HeaderHash>) -> ExternResult<SensemakerEnt
         .into_iter()
         .map(SensemakerOperand::SensemakerOperand)
         .collect();
-    mk_sensemaker_entry(full_lam, operands)
+    mk_sensemaker_entry(full_lam, operands, None)
 }
 
 /// assumes that the first `HeaderHash` is the operator, and that successive
@@ -251,12 +473,25 @@ pub fn pack_ses_into_list_se(ses: Vec<HeaderHash>) -> ExternResult<SensemakerEnt
 pub fn mk_application_se(hh_s: Vec<HeaderHash>) -> ExternResult<SensemakerEntry> {
     // there must be at least an operator
     if hh_s.len() <= 1 {
-        return Err(WasmError::Guest("no operator provided".into()));
+        return Err(SensemakerError::InvalidInput {
+            message: "no operator provided".into(),
+        }
+        .into());
     }
 
+    let full_lam = application_expr(hh_s.len());
+    let operands = hh_s
+        .into_iter()
+        .map(SensemakerOperand::SensemakerOperand)
+        .collect();
+    mk_sensemaker_entry(full_lam, operands, None)
+}
+
+/// `\x0 x1 .. xn -> x0 x1 .. xn`, which applies its first argument to the rest.
+fn application_expr(arity: usize) -> Expr {
     let mut es = EvalState::new();
 
-    let fresh_names: Vec<Name> = hh_s.iter().map(|_| es.fresh_name()).collect();
+    let fresh_names: Vec<Name> = (0..arity).map(|_| es.fresh_name()).collect();
 
     let apply_vars = |acc, nm: &Name| app!(acc, Expr::Var(nm.clone()));
     // we pull out the operator, so it may be applied to the others
@@ -267,91 +502,219 @@ pub fn mk_application_se(hh_s: Vec<HeaderHash>) -> ExternResult<SensemakerEntry>
     // create the outer lambda, successively wrapping a lambda which
     // binds each fresh name.
     let wrap_lambda = |acc, nm| lam!(nm, acc);
-    let full_lam = fresh_names.into_iter().rev().fold(app_body, wrap_lambda);
-
-    let operands = hh_s
-        .into_iter()
-        .map(SensemakerOperand::SensemakerOperand)
-        .collect();
-    mk_sensemaker_entry(full_lam, operands)
+    fresh_names.into_iter().rev().fold(app_body, wrap_lambda)
 }
 
 #[hdk_extern]
 pub fn get_sensemaker_entry(arg_hash: EntryHash) -> ExternResult<(HeaderHash, SensemakerEntry)> {
-    let element = (match get(arg_hash.clone(), GetOptions::content())? {
-        Some(el) => Ok(el),
-        None => Err(WasmError::Guest(format!(
-            "could not dereference arg: {}",
-            arg_hash
-        ))),
-    })?;
-    let hh = element.header_address().clone();
-    match element.into_inner().1.to_app_option()? {
-        Some(se) => Ok((hh, se)),
-        None => Err(WasmError::Guest(format!("non-present arg: {}", arg_hash))),
-    }
+    let (se, hh) = util::try_get_and_convert_with_hh(arg_hash, GetOptions::content())?;
+    Ok((hh, se))
 }
 
-pub fn mk_sensemaker_entry_parse(expr_str: String) -> ExternResult<SensemakerEntry> {
-    match expr().easy_parse(position::Stream::new(&expr_str[..])) {
-        Err(err) => Err(WasmError::Guest(format!("parse error:\n\n{}\n", err))),
+/// parse a rep_lang expression, which must consume all of `expr_str`.
+pub fn parse_expr(expr_str: &str) -> ExternResult<Expr> {
+    match expr().easy_parse(position::Stream::new(expr_str)) {
+        Err(err) => Err(SensemakerError::Parse {
+            input: expr_str.into(),
+            message: format!("{}", err),
+        }
+        .into()),
         Ok((expr, extra_input)) => {
             if extra_input.is_partial() {
-                Err(WasmError::Guest(format!(
-                    "error: unconsumed input: {:?}",
-                    extra_input
-                )))
+                Err(SensemakerError::Parse {
+                    input: expr_str.into(),
+                    message: format!("unconsumed input: {:?}", extra_input),
+                }
+                .into())
             } else {
-                mk_sensemaker_entry(expr, vec![])
+                Ok(expr)
             }
         }
     }
 }
 
+pub fn mk_sensemaker_entry_parse(expr_str: String) -> ExternResult<SensemakerEntry> {
+    mk_sensemaker_entry(parse_expr(&expr_str)?, vec![], None)
+}
+
+/// computes a `SensemakerEntry`, rejecting it if it exceeds the DNA's gas budget or
+/// size limits (see `properties::SensemakerProperties`), or if its `output_scheme` does
+/// not unify with `opt_expected_sc`.
 pub fn mk_sensemaker_entry(
     expr: Expr,
     args: Vec<SensemakerOperand>,
+    opt_expected_sc: Option<Scheme>,
 ) -> ExternResult<SensemakerEntry> {
-    let arg_hh_s: Vec<HeaderHash> = args
-        .iter()
-        .map(|io| match io {
-            SensemakerOperand::SensemakerOperand(hh) => hh.clone(),
-            SensemakerOperand::OtherOperand(_) => todo!("OtherOperand"),
-        })
-        .collect();
-
-    // don't need result, just a preliminary check before hitting DHT
-    let _expr_sc =
-        infer_expr_with_is(&Env::new(), &mut InferState::new(), &expr).map_err(|type_error| {
-            WasmError::Guest(format!("type error in `expr`: {:?}", type_error))
+    let (output_scheme, output_flat_value, start_gas) =
+        eval_within_limits(&expr, args.len(), || {
+            args.iter().map(resolve_operand).collect()
         })?;
+    if let Some(expected_sc) = opt_expected_sc {
+        check_expected_scheme(expected_sc, &output_scheme)?;
+    }
+    Ok(SensemakerEntry {
+        operator: expr,
+        operands: args,
+        output_scheme,
+        output_flat_value,
+        start_gas,
+    })
+}
+
+/// type-check & evaluate `expr`, applied to the operands which `resolve_args` yields,
+/// enforcing the DNA's limits (see `properties::SensemakerProperties`). the size limits
+/// are checked before any operand is resolved, the gas budget during evaluation, and the
+/// value limit after it.
+fn eval_within_limits<F>(
+    expr: &Expr,
+    num_operands: usize,
+    resolve_args: F,
+) -> ExternResult<(Scheme, FlatValue<Marker>, Gas)>
+where
+    F: FnOnce() -> ExternResult<Vec<(Scheme, FlatValue<Marker>)>>,
+{
+    if let Some(err) = input_limit_violation(expr, num_operands)? {
+        return Err(err.into());
+    }
+    infer_operator(expr)?;
+    let max_gas = sensemaker_properties()?.max_gas;
+    let (sc, flat_val, gas) = eval_application(expr, resolve_args()?, max_gas)?;
+    if let Some(err) = flat_value_limit_violation(&flat_val)? {
+        return Err(err.into());
+    }
+    Ok((sc, flat_val, gas))
+}
+
+/// a preliminary type-check of the operator alone, so that an ill-typed operator fails
+/// before any operand is dereferenced.
+fn infer_operator(expr: &Expr) -> ExternResult<Scheme> {
+    infer_expr_with_is(&Env::new(), &mut InferState::new(), expr).map_err(|type_error| {
+        SensemakerError::Type {
+            context: "`expr`".into(),
+            message: format!("{:?}", type_error),
+        }
+        .into()
+    })
+}
+
+fn check_expected_scheme(expected_sc: Scheme, actual_sc: &Scheme) -> ExternResult<()> {
+    let mut is = InferState::new();
+    let Scheme(_, expected_ty) = normalize(&mut is, expected_sc.clone());
+    let Scheme(_, actual_ty) = normalize(&mut is, actual_sc.clone());
+    match unifies(expected_ty, actual_ty) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(SensemakerError::SchemeMismatch {
+            expected: expected_sc,
+            actual: actual_sc.clone(),
+        }
+        .into()),
+    }
+}
 
-    // dereference `arg_hh_s`
-    let int_entrs: Vec<SensemakerEntry> = arg_hh_s
+/// computes a `SensemakerEntry`, giving up once evaluation has spent more than `max_gas`.
+/// an overrun is returned as the inner `Err`, rather than failing, so that validation
+/// can produce an `Invalid`.
+pub fn compute_sensemaker_entry(
+    expr: Expr,
+    args: Vec<SensemakerOperand>,
+    max_gas: Gas,
+) -> ExternResult<Result<SensemakerEntry, SensemakerError>> {
+    infer_operator(&expr)?;
+
+    // dereference `args`
+    let arg_scheme_values: Vec<(Scheme, FlatValue<Marker>)> = args
         .iter()
-        .cloned()
-        .map(|arg_hash| {
-            let element = must_get_valid_element(arg_hash.clone())?;
-            match element.into_inner().1.to_app_option()? {
-                Some(se) => Ok(se),
-                None => Err(WasmError::Guest(format!("non-present arg: {}", arg_hash))),
-            }
-        })
+        .map(resolve_operand)
         .collect::<ExternResult<_>>()?;
 
+    let (output_scheme, output_flat_value, start_gas, _trace) =
+        match eval_application_bounded(&expr, arg_scheme_values, Some(max_gas))? {
+            Ok(evaluated) => evaluated,
+            Err(err) => return Ok(Err(err)),
+        };
+
+    let new_se: SensemakerEntry = SensemakerEntry {
+        operator: expr,
+        operands: args,
+        output_scheme,
+        output_flat_value,
+        start_gas,
+    };
+    Ok(Ok(new_se))
+}
+
+/// type-check & evaluate `expr` applied to each of `arg_scheme_values`, in order.
+/// returns the `Scheme` & value of the full application, and the gas it consumed.
+/// fails with `GasBudgetExceeded` once more than `max_gas` (if any) has been spent.
+pub fn eval_application(
+    expr: &Expr,
+    arg_scheme_values: Vec<(Scheme, FlatValue<Marker>)>,
+    max_gas: Option<Gas>,
+) -> ExternResult<(Scheme, FlatValue<Marker>, Gas)> {
+    let (sc, flat_val, gas, _trace) = eval_application_traced(expr, arg_scheme_values, max_gas)?;
+    Ok((sc, flat_val, gas))
+}
+
+/// how `eval_application` arrived at its result. the gas of the three phases sums to
+/// the total it returns.
+#[derive(Debug, Clone)]
+pub struct EvalTrace {
+    /// the fresh name, `Scheme` & value which each operand was bound to.
+    pub bindings: Vec<(Name, Scheme, FlatValue<Marker>)>,
+    /// the normalized operator.
+    pub operator: Expr,
+    /// the operator, applied to each binding's name.
+    pub full_application: Expr,
+    /// spent normalizing the operator & operands, and binding the latter.
+    pub setup_gas: Gas,
+    /// spent evaluating `full_application`.
+    pub eval_gas: Gas,
+    /// spent forcing the (lazily evaluated) result to a `FlatValue`.
+    pub force_gas: Gas,
+}
+
+/// `eval_application`, also returning an `EvalTrace`.
+pub fn eval_application_traced(
+    expr: &Expr,
+    arg_scheme_values: Vec<(Scheme, FlatValue<Marker>)>,
+    max_gas: Option<Gas>,
+) -> ExternResult<(Scheme, FlatValue<Marker>, Gas, EvalTrace)> {
+    eval_application_bounded(expr, arg_scheme_values, max_gas)?.map_err(WasmError::from)
+}
+
+/// `eval_application_traced`, with a gas overrun as the inner `Err`.
+///
+/// NB: `eval_` cannot be interrupted from outside of rep_lang, so the budget is checked
+/// between the phases of evaluation (see `EvalTrace`). since evaluation is lazy, the
+/// bulk of the work is usually forcing the result, which is skipped once `eval_` alone
+/// has overrun.
+fn eval_application_bounded(
+    expr: &Expr,
+    arg_scheme_values: Vec<(Scheme, FlatValue<Marker>)>,
+    max_gas: Option<Gas>,
+) -> ExternResult<Result<(Scheme, FlatValue<Marker>, Gas, EvalTrace), SensemakerError>> {
+    let overrun = |es: &EvalState| match max_gas {
+        Some(max_gas) if es.current_gas_count() > max_gas => {
+            Some(SensemakerError::GasBudgetExceeded {
+                used: es.current_gas_count(),
+                max_gas,
+            })
+        }
+        _ => None,
+    };
     let mut is = InferState::new();
     let mut es = EvalState::new();
     let mut type_env = Env::new();
     // we normalize up here, before conjuring fresh names for the `args`, in order to avoid
     // potential contamination. I'm not sure it is necessary, but doing it to be safe.
     let normalized_expr = expr.normalize(&mut HashMap::new(), &mut es);
-    let arg_named_scheme_values: Vec<(Name, Scheme, FlatValue<Marker>)> = int_entrs
-        .iter()
-        .map(|se| {
+    let arg_named_scheme_values: Vec<(Name, Scheme, FlatValue<Marker>)> = arg_scheme_values
+        .into_iter()
+        .map(|(sc, flat_val)| {
             (
                 es.fresh_name(),
-                infer::normalize(&mut is, se.output_scheme.clone()),
-                se.output_flat_value.normalize(&mut HashMap::new(), &mut es),
+                infer::normalize(&mut is, sc),
+                flat_val.normalize(&mut HashMap::new(), &mut es),
             )
         })
         .collect();
@@ -365,13 +728,16 @@ pub fn mk_sensemaker_entry(
     let full_application: Expr = arg_named_scheme_values
         .iter()
         .map(|t| t.0.clone())
-        .fold(normalized_expr, applicator);
+        .fold(normalized_expr.clone(), applicator);
 
     // TODO substantiate whether this Scheme will have high-indexed `Tv`s, which might be
     // unintuitive / cause issues with programmatic `Scheme` matching.
     let full_application_sc =
         infer_expr_with_is(&type_env, &mut is, &full_application).map_err(|type_error| {
-            WasmError::Guest(format!("type error in full application: {:?}", type_error))
+            SensemakerError::Type {
+                context: "full application".into(),
+                message: format!("{:?}", type_error),
+            }
         })?;
 
     let mut term_env = new_term_env();
@@ -385,22 +751,108 @@ pub fn mk_sensemaker_entry(
             flat_thunk_to_sto_ref(&mut es, &mut sto, inject_flatvalue_to_flatthunk(flat_val));
         term_env.insert(nm, v_ref);
     }
+    let setup_gas = es.current_gas_count();
+    if let Some(err) = overrun(&es) {
+        return Ok(Err(err));
+    }
 
     let full_application_vr = eval_(&term_env, &mut sto, &mut es, &full_application);
+    let eval_gas = es.current_gas_count() - setup_gas;
+    if let Some(err) = overrun(&es) {
+        return Ok(Err(err));
+    }
+
     let full_application_val = lookup_sto(&mut es, &full_application_vr, &mut sto);
+    if let Some(err) = overrun(&es) {
+        return Ok(Err(err));
+    }
     let full_application_flat_val = value_to_flat_value(&mut es, &full_application_val, &mut sto);
+    let force_gas = es.current_gas_count() - setup_gas - eval_gas;
+    if let Some(err) = overrun(&es) {
+        return Ok(Err(err));
+    }
 
-    let new_se: SensemakerEntry = SensemakerEntry {
-        operator: expr,
-        operands: arg_hh_s
-            .into_iter()
-            .map(SensemakerOperand::SensemakerOperand)
-            .collect(),
-        output_scheme: full_application_sc,
-        output_flat_value: full_application_flat_val,
-        start_gas: es.current_gas_count(),
+    let trace = EvalTrace {
+        bindings: arg_named_scheme_values,
+        operator: normalized_expr,
+        full_application,
+        setup_gas,
+        eval_gas,
+        force_gas,
+    };
+    Ok(Ok((
+        full_application_sc,
+        full_application_flat_val,
+        es.current_gas_count(),
+        trace,
+    )))
+}
+
+/// dereference a `SensemakerOperand` to the `Scheme` & `FlatValue` which it contributes
+/// to an application.
+pub fn resolve_operand(operand: &SensemakerOperand) -> ExternResult<(Scheme, FlatValue<Marker>)> {
+    match operand {
+        SensemakerOperand::SensemakerOperand(arg_hash) => {
+            let element = must_get_valid_element(arg_hash.clone())?;
+            let se: SensemakerEntry = util::try_from_element(element)?;
+            Ok((se.output_scheme, se.output_flat_value))
+        }
+        SensemakerOperand::OtherOperand(arg_hash) => {
+            let sb = other_operand_bytes(arg_hash)?;
+            if let Some(err) = other_operand_limit_violation(sb.bytes().len())? {
+                return Err(err.into());
+            }
+            Ok(bytes_to_scheme_flat_value(sb.bytes()))
+        }
+    }
+}
+
+/// the serialized bytes of the app entry which an `OtherOperand` references.
+fn other_operand_bytes(arg_hash: &HeaderHash) -> ExternResult<SerializedBytes> {
+    let element = must_get_valid_element(arg_hash.clone())?;
+    match element.into_inner().1.into_option() {
+        Some(Entry::App(entry_bytes)) => Ok(SerializedBytes::from(entry_bytes)),
+        _ => Err(SensemakerError::NonPresent {
+            hash: arg_hash.to_string(),
+            expected: "app entry".into(),
+        }
+        .into()),
+    }
+}
+
+/// `Some(err)` if any `OtherOperand` among `operands` is too large to expose as a list.
+/// validation checks this before resolving the operands, so that such an entry is
+/// `Invalid` rather than failing to resolve.
+pub fn operands_limit_violation(
+    operands: &[SensemakerOperand],
+) -> ExternResult<Option<SensemakerError>> {
+    for operand in operands {
+        if let SensemakerOperand::OtherOperand(arg_hash) = operand {
+            let num_bytes = other_operand_bytes(arg_hash)?.bytes().len();
+            if let Some(err) = other_operand_limit_violation(num_bytes)? {
+                return Ok(Some(err));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// foreign entries have no rep_lang representation of their own, so we expose their
+/// serialized bytes as a `List Int` (one element per byte, in order).
+///
+/// the list is built directly, rather than by evaluating a `Cons` expression, as that
+/// expression would nest as deeply as there are bytes. the list is still nested that
+/// deeply, which is why `resolve_operand` bounds its length.
+pub fn bytes_to_scheme_flat_value(bytes: &[u8]) -> (Scheme, FlatValue<Marker>) {
+    let sc = Scheme(vec![], Type::TList(Box::new(Type::TCon("Int".into()))));
+    let cons_byte = |acc, byte: &u8| {
+        FlatValue(Value::VCons(
+            Box::new(FlatValue(Value::VInt(i64::from(*byte)))),
+            Box::new(acc),
+        ))
     };
-    Ok(new_se)
+    let list_flat_val = bytes.iter().rev().fold(FlatValue(Value::VNil), cons_byte);
+    (sc, list_flat_val)
 }
 
 #[derive(Debug, Serialize, Deserialize, SerializedBytes)]
@@ -409,39 +861,115 @@ pub struct CreateSensemakerEntryInputParse {
     pub args: Vec<String>,
 }
 
-/// INFO this is incomplete and doesn't currently parse the `args`
+/// `CreateSensemakerEntryInputParse.args` are parsed as `<prefix><HeaderHash>`, where
+/// the prefix selects the `SensemakerOperand` constructor & the hash is in its usual
+/// base64 string form.
+pub const SENSEMAKER_OPERAND_PREFIX: &str = "se:";
+pub const OTHER_OPERAND_PREFIX: &str = "other:";
+
+pub fn parse_operand(arg_idx: usize, arg: &str) -> ExternResult<SensemakerOperand> {
+    let (constructor, hash_str): (fn(HeaderHash) -> SensemakerOperand, &str) =
+        if let Some(hash_str) = arg.strip_prefix(SENSEMAKER_OPERAND_PREFIX) {
+            (SensemakerOperand::SensemakerOperand, hash_str)
+        } else if let Some(hash_str) = arg.strip_prefix(OTHER_OPERAND_PREFIX) {
+            (SensemakerOperand::OtherOperand, hash_str)
+        } else {
+            return Err(SensemakerError::OperandParse {
+                arg_idx,
+                arg: arg.into(),
+                message: format!(
+                    "lacks an operand prefix (expected `{}` or `{}`)",
+                    SENSEMAKER_OPERAND_PREFIX, OTHER_OPERAND_PREFIX
+                ),
+            }
+            .into());
+        };
+    match HeaderHashB64::from_b64_str(hash_str) {
+        Ok(hh_b64) => Ok(constructor(HeaderHash::from(hh_b64))),
+        Err(err) => Err(SensemakerError::OperandParse {
+            arg_idx,
+            arg: arg.into(),
+            message: format!("{:?} is not a valid HeaderHash: {}", hash_str, err),
+        }
+        .into()),
+    }
+}
+
 #[hdk_extern]
 pub fn create_sensemaker_entry_parse(
     input: CreateSensemakerEntryInputParse,
 ) -> ExternResult<(HeaderHash, SensemakerEntry)> {
-    let (hh, _eh, se) = match expr().easy_parse(position::Stream::new(&input.expr[..])) {
-        Err(err) => Err(WasmError::Guest(format!("parse error:\n\n{}\n", err))),
-        Ok((expr, extra_input)) => {
-            if extra_input.is_partial() {
-                Err(WasmError::Guest(format!(
-                    "error: unconsumed input: {:?}",
-                    extra_input
-                )))
-            } else {
-                create_sensemaker_entry_full(CreateSensemakerEntryInput {
-                    expr,
-                    // TODO parse `input.args`.
-                    // parsing a HeaderHash seems like it should be possible, but I've not done
-                    // it before. we might also want some indicator of which of
-                    // SensemakerOperand constructor is desired?
-                    args: Vec::new(),
-                })
-            }
-        }
-    }?;
+    let args: Vec<SensemakerOperand> = input
+        .args
+        .iter()
+        .enumerate()
+        .map(|(arg_idx, arg)| parse_operand(arg_idx, arg))
+        .collect::<ExternResult<_>>()?;
+    let expr = parse_expr(&input.expr)?;
+    let (hh, _eh, se) = create_sensemaker_entry_full(CreateSensemakerEntryInput {
+        expr,
+        args,
+        expected_scheme: None,
+    })?;
     Ok((hh, se))
 }
 
 pub fn create_sensemaker_entry_full(
     input: CreateSensemakerEntryInput,
 ) -> ExternResult<(HeaderHash, EntryHash, SensemakerEntry)> {
-    let se = mk_sensemaker_entry(input.expr, input.args)?;
+    let se = mk_sensemaker_entry(input.expr, input.args, input.expected_scheme)?;
+    commit_sensemaker_entry(se)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SensemakerEntryPreview {
+    pub output_scheme: Scheme,
+    pub output_flat_value: FlatValue<Marker>,
+    pub start_gas: Gas,
+}
+
+/// computes the `SensemakerEntry` which `create_sensemaker_entry` would commit for
+/// `input`, without creating any entries or links.
+#[hdk_extern]
+pub fn preview_sensemaker_entry(
+    input: CreateSensemakerEntryInput,
+) -> ExternResult<SensemakerEntryPreview> {
+    let se = mk_sensemaker_entry(input.expr, input.args, input.expected_scheme)?;
+    Ok(SensemakerEntryPreview {
+        output_scheme: se.output_scheme,
+        output_flat_value: se.output_flat_value,
+        start_gas: se.start_gas,
+    })
+}
+
+/// commits an already-computed `SensemakerEntry`, along with its Scheme index &
+/// reverse-dependency links. if an identical entry already exists, it is reused.
+pub fn commit_sensemaker_entry(
+    se: SensemakerEntry,
+) -> ExternResult<(HeaderHash, EntryHash, SensemakerEntry)> {
+    let scheme_entry_hash = ensure_scheme_entry(&se.output_scheme)?;
+
+    // create SE & link from Scheme entry (if needed)
+    let se_eh = hash_entry(&se)?;
+    match get(se_eh.clone(), GetOptions::content())? {
+        None => {
+            let hh = create_entry(&se)?;
+            create_link(
+                scheme_entry_hash,
+                se_eh.clone(),
+                LinkType::new(0),
+                LinkTag::new(OWNER_TAG),
+            )?;
+            link_dependents(&hh, &se)?;
+            Ok((hh, se_eh, se))
+        }
+        Some(element) => Ok((element.header_address().clone(), se_eh, se)),
+    }
+}
 
+/// creates the `SchemeRoot`, `SchemeBucket` & `SchemeEntry` for `sc`, and the links
+/// between them, as needed. returns the hash of the `SchemeEntry`.
+fn ensure_scheme_entry(sc: &Scheme) -> ExternResult<EntryHash> {
     // create SchemeRoot (if needed)
     match get(hash_entry(&SchemeRoot)?, GetOptions::content())? {
         None => {
@@ -450,83 +978,460 @@ pub fn create_sensemaker_entry_full(
         Some(_) => {}
     };
 
-    // create Scheme entry & link from SchemeRoot (if needed)
-    let scheme_entry = SchemeEntry {
-        sc: se.output_scheme.clone(),
+    // create SchemeBucket & link from SchemeRoot (if needed)
+    let scheme_bucket = SchemeBucket {
+        key: scheme_bucket_key(sc),
     };
-    let scheme_entry_hash = hash_entry(&scheme_entry)?;
-    match get(scheme_entry_hash.clone(), GetOptions::content())? {
+    let scheme_bucket_hash = hash_entry(&scheme_bucket)?;
+    match get(scheme_bucket_hash.clone(), GetOptions::content())? {
         None => {
-            let _hh = create_entry(&scheme_entry)?;
+            let _hh = create_entry(&scheme_bucket)?;
             create_link(
                 hash_entry(SchemeRoot)?,
-                scheme_entry_hash.clone(),
+                scheme_bucket_hash.clone(),
                 LinkType::new(0),
-                LinkTag::new(OWNER_TAG),
+                LinkTag::new(SCHEME_BUCKET_TAG),
             )?;
         }
         Some(_) => {}
     };
 
-    // create SE & link from Scheme entry (if needed)
-    let se_eh = hash_entry(&se)?;
-    match get(se_eh.clone(), GetOptions::content())? {
+    // create Scheme entry & link from SchemeBucket (if needed)
+    let scheme_entry = SchemeEntry { sc: sc.clone() };
+    let scheme_entry_hash = hash_entry(&scheme_entry)?;
+    match get(scheme_entry_hash.clone(), GetOptions::content())? {
         None => {
-            let hh = create_entry(&se)?;
+            let _hh = create_entry(&scheme_entry)?;
             create_link(
-                scheme_entry_hash,
-                se_eh.clone(),
+                scheme_bucket_hash,
+                scheme_entry_hash.clone(),
                 LinkType::new(0),
                 LinkTag::new(OWNER_TAG),
             )?;
-            Ok((hh, se_eh, se))
         }
-        Some(element) => Ok((element.header_address().clone(), se_eh, se)),
-    }
+        Some(_) => {}
+    };
+
+    Ok(scheme_entry_hash)
 }
 
-#[hdk_extern]
-pub fn get_sensemaker_entry_by_headerhash(
-    arg_hash: HeaderHash,
-) -> ExternResult<(EntryHash, SensemakerEntry)> {
-    let element = (match get(arg_hash.clone(), GetOptions::content())? {
-        Some(el) => Ok(el),
-        None => Err(WasmError::Guest(format!(
-            "could not dereference arg: {}",
-            arg_hash
-        ))),
-    })?;
-    match element.into_inner().1.to_app_option()? {
-        Some(se) => {
-            let se_hash = hash_entry(&se)?;
-            Ok((se_hash, se))
-        }
-        None => Err(WasmError::Guest(format!("non-present arg: {}", arg_hash))),
+/// reverse-dependency index, from each operand to the SE at `hh`
+fn link_dependents(hh: &HeaderHash, se: &SensemakerEntry) -> ExternResult<()> {
+    for operand in &se.operands {
+        create_link(
+            operand.header_hash().clone(),
+            hh.clone(),
+            LinkType::new(0),
+            LinkTag::new(DEPENDENT_TAG),
+        )?;
     }
+    Ok(())
 }
 
-pub fn get_latest_path_entry(
-    path_string: String,
-    link_tag_string: String,
-) -> ExternResult<Option<EntryHash>> {
-    let path = Path::from(path_string);
-    get_latest_linked_entry(path.path_entry_hash()?, link_tag_string)
+/// computes a new revision of the `SensemakerEntry` at `original_hh` & commits it as
+/// an update. the Scheme index link is moved from the original entry to the new one,
+/// changing `SchemeEntry` if the update changed the `output_scheme`.
+pub fn update_sensemaker_entry_full(
+    original_hh: HeaderHash,
+    input: CreateSensemakerEntryInput,
+) -> ExternResult<(HeaderHash, EntryHash, SensemakerEntry)> {
+    let (original_eh, original_se) = get_sensemaker_entry_by_headerhash(original_hh.clone())?;
+    let se = mk_sensemaker_entry(input.expr, input.args, input.expected_scheme)?;
+    let se_eh = hash_entry(&se)?;
+
+    let scheme_entry_hash = ensure_scheme_entry(&se.output_scheme)?;
+    let hh = update_entry(original_hh, &se)?;
+    if se_eh != original_eh {
+        unlink_scheme_entry(&original_se.output_scheme, original_eh)?;
+        create_link(
+            scheme_entry_hash,
+            se_eh.clone(),
+            LinkType::new(0),
+            LinkTag::new(OWNER_TAG),
+        )?;
+    }
+    link_dependents(&hh, &se)?;
+    Ok((hh, se_eh, se))
 }
 
-pub fn get_latest_linked_entry(
-    target: EntryHash,
+/// deletes the Scheme index link(s) from the `SchemeEntry` of `sc` to `se_eh`.
+fn unlink_scheme_entry(sc: &Scheme, se_eh: EntryHash) -> ExternResult<()> {
+    let scheme_entry_hash = hash_entry(&SchemeEntry { sc: sc.clone() })?;
+    for lnk in get_links_exact(scheme_entry_hash, OWNER_TAG)? {
+        if lnk.target.into_entry_hash() == Some(se_eh.clone()) {
+            delete_link(lnk.create_link_hash)?;
+        }
+    }
+    Ok(())
+}
+
+/// deletes the `SensemakerEntry` at `hh`, along with its Scheme index link. validation
+/// only permits the author of an entry to delete it.
+///
+/// the reverse-dependency links are kept, so that `get_dependents` can report the
+/// entries which consumed it.
+pub fn delete_sensemaker_entry_full(hh: HeaderHash) -> ExternResult<HeaderHash> {
+    let (se_eh, se) = get_sensemaker_entry_by_headerhash(hh.clone())?;
+    unlink_scheme_entry(&se.output_scheme, se_eh)?;
+    delete_entry(hh)
+}
+
+/// whether the element at `hh` has been deleted.
+fn is_deleted(hh: HeaderHash) -> ExternResult<bool> {
+    match get_details(hh.clone(), GetOptions::content())? {
+        Some(Details::Element(element_details)) => Ok(!element_details.deletes.is_empty()),
+        _ => Err(SensemakerError::Dereference {
+            hash: hh.to_string(),
+        }
+        .into()),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SensemakerEntryRevision {
+    pub hh: HeaderHash,
+    /// the revision which this one updates. `None` for the original `Create`.
+    pub replaces: Option<HeaderHash>,
+    pub author: AgentPubKey,
+    pub timestamp: Timestamp,
+    pub se: SensemakerEntry,
+}
+
+/// the update history of the `SensemakerEntry` at `hh`: the original `Create` & every
+/// (transitive) update of it, sorted by timestamp. `hh` may be any revision.
+///
+/// concurrent updates of the same revision are all included; `replaces` can be used to
+/// reconstruct the tree.
+#[hdk_extern]
+pub fn get_sensemaker_entry_history(hh: HeaderHash) -> ExternResult<Vec<SensemakerEntryRevision>> {
+    // walk back to the original `Create`
+    let mut original_hh = hh;
+    while let Header::Update(update) = get_element_by_headerhash(original_hh.clone())?.header() {
+        original_hh = update.original_header_address.clone();
+    }
+
+    // walk forward through the updates
+    let mut revisions: Vec<SensemakerEntryRevision> = Vec::new();
+    let mut queue: Vec<(HeaderHash, Option<HeaderHash>)> = vec![(original_hh, None)];
+    while let Some((rev_hh, replaces)) = queue.pop() {
+        let element_details = match get_details(rev_hh.clone(), GetOptions::content())? {
+            Some(Details::Element(element_details)) => element_details,
+            _ => {
+                return Err(SensemakerError::Dereference {
+                    hash: rev_hh.to_string(),
+                }
+                .into())
+            }
+        };
+        for update in element_details.updates.iter() {
+            queue.push((update.header_address().clone(), Some(rev_hh.clone())));
+        }
+        let header = element_details.element.header().clone();
+        let se: SensemakerEntry = util::try_from_element(element_details.element)?;
+        revisions.push(SensemakerEntryRevision {
+            hh: rev_hh,
+            replaces,
+            author: header.author().clone(),
+            timestamp: header.timestamp(),
+            se,
+        });
+    }
+    revisions.sort_by_key(|rev| rev.timestamp);
+    Ok(revisions)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum BatchOperand {
+    /// an operand which is already on the DHT
+    Existing(SensemakerOperand),
+    /// the `SensemakerEntry` created by an earlier item of the same batch
+    Index(usize),
+}
+
+/// an item of the input to `create_sensemaker_entries_batch`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchSensemakerEntryInput {
+    pub expr: Expr,
+    pub args: Vec<BatchOperand>,
+}
+
+/// creates a DAG of `SensemakerEntry`s within a single zome call, returning their
+/// `HeaderHash`es in input order.
+///
+/// every item is type-checked & evaluated before anything is committed, with
+/// `BatchOperand::Index` operands resolved in memory. should any item fail, the zome
+/// call fails & none of the batch is committed.
+#[hdk_extern]
+pub fn create_sensemaker_entries_batch(
+    items: Vec<BatchSensemakerEntryInput>,
+) -> ExternResult<Vec<HeaderHash>> {
+    let mut computed: Vec<(Scheme, FlatValue<Marker>, Gas)> = Vec::new();
+    for (idx, item) in items.iter().enumerate() {
+        let item_computed =
+            compute_batch_item(item, &computed).map_err(|err| SensemakerError::BatchItem {
+                index: idx,
+                error: Box::new(err.into()),
+            })?;
+        computed.push(item_computed);
+    }
+
+    let mut hh_s: Vec<HeaderHash> = Vec::new();
+    for (item, (output_scheme, output_flat_value, start_gas)) in items.into_iter().zip(computed) {
+        let operands = item
+            .args
+            .into_iter()
+            .map(|arg| match arg {
+                BatchOperand::Existing(operand) => operand,
+                // indices were checked above
+                BatchOperand::Index(arg_idx) => {
+                    SensemakerOperand::SensemakerOperand(hh_s[arg_idx].clone())
+                }
+            })
+            .collect();
+        let se = SensemakerEntry {
+            operator: item.expr,
+            operands,
+            output_scheme,
+            output_flat_value,
+            start_gas,
+        };
+        let (hh, _eh, _se) = commit_sensemaker_entry(se)?;
+        hh_s.push(hh);
+    }
+    Ok(hh_s)
+}
+
+/// evaluate a batch item, given the results of the items before it.
+fn compute_batch_item(
+    item: &BatchSensemakerEntryInput,
+    computed: &[(Scheme, FlatValue<Marker>, Gas)],
+) -> ExternResult<(Scheme, FlatValue<Marker>, Gas)> {
+    let resolve_args = || {
+        item.args
+            .iter()
+            .map(|arg| match arg {
+                BatchOperand::Existing(operand) => resolve_operand(operand),
+                BatchOperand::Index(arg_idx) => match computed.get(*arg_idx) {
+                    Some((sc, flat_val, _gas)) => Ok((sc.clone(), flat_val.clone())),
+                    None => Err(SensemakerError::InvalidInput {
+                        message: format!(
+                            "operand index {} does not refer to an earlier item",
+                            arg_idx
+                        ),
+                    }
+                    .into()),
+                },
+            })
+            .collect()
+    };
+    eval_within_limits(&item.expr, item.args.len(), resolve_args)
+}
+
+/// input to `get_dependents`
+#[derive(Debug, Serialize, Deserialize, SerializedBytes)]
+pub struct GetDependentsInput {
+    pub hh: HeaderHash,
+    /// how many hops of dependents to follow. `1` yields only direct dependents.
+    pub max_depth: usize,
+    /// how many dependents (in breadth-first order) to skip.
+    pub offset: usize,
+    /// the most dependents to return.
+    pub limit: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Dependent {
+    pub hh: HeaderHash,
+    /// number of hops from the queried entry. direct dependents have depth `1`.
+    pub depth: usize,
+    /// the operands of this dependent which have since been deleted. its value was
+    /// computed from them, so it may no longer be meaningful.
+    pub retracted_inputs: Vec<HeaderHash>,
+}
+
+/// walks the reverse-dependency links (created by `create_sensemaker_entry_full`)
+/// breadth-first, returning the `SensemakerEntry`s which consume `input.hh`, directly or
+/// transitively.
+#[hdk_extern]
+pub fn get_dependents(input: GetDependentsInput) -> ExternResult<Vec<Dependent>> {
+    let wanted = input.offset.saturating_add(input.limit);
+    let mut seen: HashSet<HeaderHash> = HashSet::new();
+    seen.insert(input.hh.clone());
+    let mut frontier: Vec<HeaderHash> = vec![input.hh];
+    let mut dependents: Vec<Dependent> = Vec::new();
+    let mut depth = 0;
+    // we stop at a depth boundary once we have enough, so the order within a page never
+    // depends on how much was asked for.
+    while !frontier.is_empty() && depth < input.max_depth && dependents.len() < wanted {
+        depth += 1;
+        let mut next_frontier = Vec::new();
+        for hh in frontier {
+            let mut links = get_links_exact(hh, DEPENDENT_TAG)?;
+            // `get_links` makes no ordering guarantees, so we impose one for the sake of
+            // stable pagination.
+            links.sort_by_key(|lnk| lnk.timestamp);
+            for dependent_hh in links
+                .into_iter()
+                .filter_map(|lnk| lnk.target.into_header_hash())
+            {
+                if seen.insert(dependent_hh.clone()) {
+                    dependents.push(Dependent {
+                        hh: dependent_hh.clone(),
+                        depth,
+                        retracted_inputs: Vec::new(),
+                    });
+                    next_frontier.push(dependent_hh);
+                }
+            }
+        }
+        frontier = next_frontier;
+    }
+    let mut page: Vec<Dependent> = dependents
+        .into_iter()
+        .skip(input.offset)
+        .take(input.limit)
+        .collect();
+    for dependent in page.iter_mut() {
+        let (_se_eh, se) = get_sensemaker_entry_by_headerhash(dependent.hh.clone())?;
+        for operand in se.operands {
+            if is_deleted(operand.header_hash().clone())? {
+                dependent
+                    .retracted_inputs
+                    .push(operand.header_hash().clone());
+            }
+        }
+    }
+    Ok(page)
+}
+
+#[hdk_extern]
+pub fn get_sensemaker_entry_by_headerhash(
+    arg_hash: HeaderHash,
+) -> ExternResult<(EntryHash, SensemakerEntry)> {
+    let element = get_element_by_headerhash(arg_hash)?;
+    let se: SensemakerEntry = util::try_from_element(element)?;
+    let se_hash = hash_entry(&se)?;
+    Ok((se_hash, se))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ProvenanceNode {
+    Sensemaker {
+        hh: HeaderHash,
+        author: AgentPubKey,
+        operator: Expr,
+        output_scheme: Scheme,
+        output_flat_value: FlatValue<Marker>,
+    },
+    // the leaves of the DAG: entries referenced via `OtherOperand`
+    Other {
+        hh: HeaderHash,
+        author: AgentPubKey,
+    },
+}
+
+/// `consumer` uses `operand` as its `index`th operand.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProvenanceEdge {
+    pub consumer: HeaderHash,
+    pub operand: HeaderHash,
+    pub index: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeProvenance {
+    /// in breadth-first order from the queried entry, which comes first.
+    pub nodes: Vec<ProvenanceNode>,
+    pub edges: Vec<ProvenanceEdge>,
+}
+
+/// follows `SensemakerEntry.operands` from `root_hh`, up to `max_depth` hops, returning
+/// every entry reached & the operand edges between them. shared operands appear once.
+#[hdk_extern]
+pub fn get_se_provenance((root_hh, max_depth): (HeaderHash, usize)) -> ExternResult<SeProvenance> {
+    let mut nodes: Vec<ProvenanceNode> = Vec::new();
+    let mut edges: Vec<ProvenanceEdge> = Vec::new();
+    let mut seen: HashSet<HeaderHash> = HashSet::new();
+    seen.insert(root_hh.clone());
+    let mut frontier: Vec<SensemakerOperand> = vec![SensemakerOperand::SensemakerOperand(root_hh)];
+    let mut depth = 0;
+    while !frontier.is_empty() {
+        let mut next_frontier = Vec::new();
+        for operand in frontier {
+            match operand {
+                SensemakerOperand::SensemakerOperand(hh) => {
+                    let element = get_element_by_headerhash(hh.clone())?;
+                    let author = element.header().author().clone();
+                    let se: SensemakerEntry = util::try_from_element(element)?;
+                    if depth < max_depth {
+                        for (index, operand) in se.operands.iter().enumerate() {
+                            let operand_hh = operand.header_hash();
+                            edges.push(ProvenanceEdge {
+                                consumer: hh.clone(),
+                                operand: operand_hh.clone(),
+                                index,
+                            });
+                            if seen.insert(operand_hh.clone()) {
+                                next_frontier.push(operand.clone());
+                            }
+                        }
+                    }
+                    nodes.push(ProvenanceNode::Sensemaker {
+                        hh,
+                        author,
+                        operator: se.operator,
+                        output_scheme: se.output_scheme,
+                        output_flat_value: se.output_flat_value,
+                    });
+                }
+                SensemakerOperand::OtherOperand(hh) => {
+                    let element = get_element_by_headerhash(hh.clone())?;
+                    let author = element.header().author().clone();
+                    nodes.push(ProvenanceNode::Other { hh, author });
+                }
+            }
+        }
+        frontier = next_frontier;
+        depth += 1;
+    }
+    Ok(SeProvenance { nodes, edges })
+}
+
+fn get_element_by_headerhash(arg_hash: HeaderHash) -> ExternResult<Element> {
+    match get(arg_hash.clone(), GetOptions::content())? {
+        Some(el) => Ok(el),
+        None => Err(SensemakerError::Dereference {
+            hash: arg_hash.to_string(),
+        }
+        .into()),
+    }
+}
+
+pub fn get_latest_path_entry(
+    path_string: String,
     link_tag_string: String,
 ) -> ExternResult<Option<EntryHash>> {
-    let links = get_links(target, Some(LinkTag::new(link_tag_string)))?;
-    match links
+    let path = Path::from(path_string);
+    get_latest_linked_entry(path.path_entry_hash()?, link_tag_string)
+}
+
+/// the target of the newest link from `target` tagged `link_tag_string`, skipping over
+/// links to entries which have since been deleted (and links to anything but an entry).
+pub fn get_latest_linked_entry(
+    target: EntryHash,
+    link_tag_string: String,
+) -> ExternResult<Option<EntryHash>> {
+    let mut links = get_links_exact(target, &link_tag_string)?;
+    links.sort_by(|x, y| y.timestamp.cmp(&x.timestamp));
+    for eh in links
         .into_iter()
-        .max_by(|x, y| x.timestamp.cmp(&y.timestamp))
+        .filter_map(|link| link.target.into_entry_hash())
     {
-        None => Ok(None),
-        Some(link) => Ok(Some(
-            link.target.into_entry_hash().expect("Should be an entry."),
-        )),
+        if get(eh.clone(), GetOptions::content())?.is_some() {
+            return Ok(Some(eh));
+        }
     }
+    Ok(None)
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -583,9 +1488,10 @@ macro_rules! sensemaker_cell_id_fns {
                         util::try_get_and_convert(entryhash.clone(), GetOptions::content())?;
                     Ok(sensemaker_cell_id_entry.to_cell_id())
                 }
-                None => Err(WasmError::Guest(
-                    "get_sensemaker_cell_id: no cell_id".into(),
-                )),
+                None => Err($crate::error::SensemakerError::Other {
+                    message: "get_sensemaker_cell_id: no cell_id".into(),
+                }
+                .into()),
             }
         }
     };
@@ -638,21 +1544,129 @@ pub fn set_sensemaker_entry(
 #[expand_remote_calls]
 pub fn set_sensemaker_entry_parse_rl_expr(
     (path_string, link_tag_string, expr_str): (String, String, String),
+) -> ExternResult<()> {
+    set_sensemaker_entry_parse_rl_expr_with_args((path_string, link_tag_string, expr_str, vec![]))
+}
+
+/// as `set_sensemaker_entry_parse_rl_expr`, with `args` parsed as per
+/// `CreateSensemakerEntryInputParse`.
+#[expand_remote_calls]
+pub fn set_sensemaker_entry_parse_rl_expr_with_args(
+    (path_string, link_tag_string, expr_str, args): (String, String, String, Vec<String>),
 ) -> ExternResult<()> {
     let (_, sensemaker_entry) = create_sensemaker_entry_parse(CreateSensemakerEntryInputParse {
         expr: expr_str,
-        args: vec![],
+        args,
     })?;
     let sensemaker_entryhash = hash_entry(sensemaker_entry)?;
 
     set_sensemaker_entry((path_string, link_tag_string, sensemaker_entryhash))
 }
 
+/// the types of a state machine: `sm_comp : data -> action -> data` & `sm_init : data`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateMachineSchemes {
+    pub data: Scheme,
+    pub action: Scheme,
+}
+
+/// check that `sm_comp` & `sm_init` agree on a data type, inferring it along with the
+/// action type.
+pub fn state_machine_schemes(
+    sm_comp_sc: Scheme,
+    sm_init_sc: Scheme,
+) -> ExternResult<StateMachineSchemes> {
+    let comp_nm = Name("sm_comp".into());
+    let init_nm = Name("sm_init".into());
+    let c_nm = Name("c".into());
+    let act_nm = Name("act".into());
+    let mut type_env = Env::new();
+    type_env.extends(vec![
+        (comp_nm.clone(), sm_comp_sc),
+        (init_nm.clone(), sm_init_sc),
+    ]);
+    // `(\c -> \act -> c (c sm_init act) act) sm_comp` is typeable iff `sm_init : data` &
+    // `sm_comp : data -> action -> data`, in which case it has type `action -> data`.
+    // `sm_comp` is passed in as `c` so that it is used at a single type: were it used
+    // directly, each use would instantiate its `Scheme` afresh, & e.g. a polymorphic
+    // `sm_comp` could step the data to another type.
+    let comp_twice = app!(
+        lam!(
+            c_nm.clone(),
+            lam!(
+                act_nm.clone(),
+                app!(
+                    app!(
+                        Expr::Var(c_nm.clone()),
+                        app!(
+                            app!(Expr::Var(c_nm), Expr::Var(init_nm)),
+                            Expr::Var(act_nm.clone())
+                        )
+                    ),
+                    Expr::Var(act_nm)
+                )
+            )
+        ),
+        Expr::Var(comp_nm)
+    );
+    let mut is = InferState::new();
+    let type_error = |message: String| SensemakerError::Type {
+        context: "state machine (sm_comp & sm_init)".into(),
+        message,
+    };
+    let Scheme(tvs, ty) = infer_expr_with_is(&type_env, &mut is, &comp_twice)
+        .map_err(|type_error_| type_error(format!("{:?}", type_error_)))?;
+    match ty {
+        Type::TArr(action_ty, data_ty) => Ok(StateMachineSchemes {
+            data: normalize(&mut is, Scheme(tvs.clone(), *data_ty)),
+            action: normalize(&mut is, Scheme(tvs, *action_ty)),
+        }),
+        ty => Err(type_error(format!("unexpected type: {:?}", ty)).into()),
+    }
+}
+
+/// creates `sm_comp` & `sm_init` from rep_lang expressions, after checking that they
+/// form a state machine, and links them from `path_prefix` with `SM_COMP_TAG` &
+/// `SM_INIT_TAG`.
+#[expand_remote_calls]
+pub fn register_state_machine(
+    (path_prefix, comp_expr_str, init_expr_str): (String, String, String),
+) -> ExternResult<StateMachineSchemes> {
+    let comp_expr = parse_expr(&comp_expr_str)?;
+    let init_expr = parse_expr(&init_expr_str)?;
+    let infer_closed = |context: &str, expr: &Expr| {
+        infer_expr_with_is(&Env::new(), &mut InferState::new(), expr).map_err(|type_error| {
+            SensemakerError::Type {
+                context: context.into(),
+                message: format!("{:?}", type_error),
+            }
+        })
+    };
+    let schemes = state_machine_schemes(
+        infer_closed("sm_comp", &comp_expr)?,
+        infer_closed("sm_init", &init_expr)?,
+    )?;
+
+    for (tag, expr) in [(SM_COMP_TAG, comp_expr), (SM_INIT_TAG, init_expr)] {
+        let (_hh, eh, _se) = create_sensemaker_entry_full(CreateSensemakerEntryInput {
+            expr,
+            args: vec![],
+            expected_scheme: None,
+        })?;
+        set_sensemaker_entry((path_prefix.clone(), tag.into(), eh))?;
+    }
+    Ok(schemes)
+}
+
 #[expand_remote_calls]
 pub fn initialize_sm_data_path((path_prefix, path_suffix): (String, String)) -> ExternResult<()> {
     let target_path_string = compose_paths(&path_prefix, &path_suffix);
-    match get_latest_path_entry(path_prefix, SM_INIT_TAG.into())? {
-        None => Err(WasmError::Guest("initialize_sm_data: no sm_init".into())),
+    match get_latest_path_entry(path_prefix.clone(), SM_INIT_TAG.into())? {
+        None => Err(SensemakerError::StateMachine {
+            path: path_prefix,
+            tag: SM_INIT_TAG.into(),
+        }
+        .into()),
         Some(init_eh) => set_sensemaker_entry((target_path_string, SM_DATA_TAG.into(), init_eh)),
     }
 }
@@ -668,48 +1682,84 @@ pub fn initialize_sm_data((path_prefix, target_eh): (String, EntryHash)) -> Exte
 pub fn step_sm_path((path_prefix, path_suffix, act): (String, String, String)) -> ExternResult<()> {
     let sm_data_path = compose_paths(&path_prefix, &path_suffix);
 
-    // fetch sm_data
-    let (sm_data_eh, _sm_data_entry) =
-        match get_sensemaker_entry_by_path((sm_data_path.clone(), "sm_data".into()))? {
-            Some(pair) => Ok(pair),
-            None => Err(WasmError::Guest("sm_data: invalid".into())),
-        }?;
+    let (sm_comp_hh, sm_comp_entry) = get_sm_comp(path_prefix)?;
 
-    // fetch sm_comp
-    let (sm_comp_eh, _sm_comp_entry) =
-        match get_sensemaker_entry_by_path((path_prefix, "sm_comp".into()))? {
-            Some(pair) => Ok(pair),
-            None => Err(WasmError::Guest("sm_comp: invalid".into())),
-        }?;
+    // check the action against `sm_comp` before anything is committed, so that a
+    // mistyped action fails with the action type it was expected to have.
+    let action_sc = sm_action_scheme(&sm_comp_entry.output_scheme)?;
+    let act_expr = parse_sm_action(&act, &action_sc)?;
 
-    let sm_comp_hh = util::get_hh(sm_comp_eh, GetOptions::content())?;
-    let sm_data_hh = util::get_hh(sm_data_eh, GetOptions::content())?;
+    // fetch sm_data, merging any concurrent steps
+    let sm_head = get_sm_head(&sm_comp_hh, sm_data_path.clone())?;
 
     // create action SensemakerEntry
-    let (act_se_hh, _act_se) = create_sensemaker_entry_parse(CreateSensemakerEntryInputParse {
-        expr: act,
+    let (act_se_hh, _act_eh, _act_se) = create_sensemaker_entry_full(CreateSensemakerEntryInput {
+        expr: act_expr,
         args: vec![],
+        expected_scheme: None,
     })?;
 
     // compose application SensemakerEntry & create it
-    let application_se = mk_application_se(vec![sm_comp_hh, sm_data_hh, act_se_hh])?;
+    let application_se = mk_application_se(vec![sm_comp_hh.clone(), sm_head.state_hh, act_se_hh])?;
     debug!("{:?}", application_se);
-    let _application_se_hh = create_entry(&application_se)?;
-    let application_se_eh = hash_entry(&application_se)?;
-    debug!("{:?}", application_se_eh);
-    {
-        let path = Path::from(sm_data_path);
-        path.ensure()?;
-        let path_hash = path.path_entry_hash()?;
-        let hh = create_link(
-            path_hash,
-            application_se_eh,
-            LinkType(0),
-            LinkTag::new("sm_data"),
-        );
-        debug!("create_link hh : {:?}", hh);
+    let (_state_hh, state_eh, _state_se) = commit_sensemaker_entry(application_se)?;
+    link_sm_data(sm_data_path.clone(), state_eh, 1)?;
+    snapshot_sm_data_if_due(&sm_comp_hh, sm_data_path, sm_head.depth + 1)
+}
+
+/// link the last of `num_states` states which were just stepped at `sm_data_path`. a
+/// single state gets a plain `SM_DATA_TAG` link, and a batch an `SmBatchTag`.
+fn link_sm_data(
+    sm_data_path: String,
+    state_eh: EntryHash,
+    num_states: usize,
+) -> ExternResult<HeaderHash> {
+    let tag = if num_states == 1 {
+        LinkTag::new(SM_DATA_TAG)
+    } else {
+        SmBatchTag { num_states }.to_link_tag()?
+    };
+    let path = Path::from(sm_data_path);
+    path.ensure()?;
+    create_link(path.path_entry_hash()?, state_eh, LinkType(0), tag)
+}
+
+/// the tag of the `sm_data` link to the last state of a `step_sm_batch_path`:
+/// `SM_DATA_TAG`, followed by this, serialized. the batch's earlier states are not
+/// linked, but are reached by following each state's `sm_data` operand back.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SmBatchTag {
+    /// the number of states which the batch stepped, the linked one included.
+    pub num_states: usize,
+}
+
+impl SmBatchTag {
+    pub fn to_link_tag(&self) -> ExternResult<LinkTag> {
+        let mut bytes = SM_DATA_TAG.as_bytes().to_vec();
+        bytes.extend(holochain_serialized_bytes::encode(self).map_err(|err| {
+            SensemakerError::Other {
+                message: format!("could not encode SmBatchTag: {:?}", err),
+            }
+        })?);
+        Ok(LinkTag::new(bytes))
     }
-    Ok(())
+
+    /// `None` if `tag` is not a well-formed batch link tag.
+    pub fn from_link_tag(tag: &LinkTag) -> Option<Self> {
+        let bytes = tag.0.strip_prefix(SM_DATA_TAG.as_bytes())?;
+        holochain_serialized_bytes::decode(bytes).ok()
+    }
+}
+
+/// the number of states which an `sm_data` link stands for: `1` for a plain
+/// `SM_DATA_TAG`, or as per its `SmBatchTag`. `None` if `tag` is neither.
+pub fn sm_data_link_num_states(tag: &LinkTag) -> Option<usize> {
+    if *tag == LinkTag::new(SM_DATA_TAG) {
+        return Some(1);
+    }
+    SmBatchTag::from_link_tag(tag)
+        .map(|batch_tag| batch_tag.num_states)
+        .filter(|num_states| *num_states > 0)
 }
 
 #[expand_remote_calls]
@@ -719,6 +1769,795 @@ pub fn step_sm((path_prefix, target_eh, act): (String, EntryHash, String)) -> Ex
     step_sm_path((path_prefix, path_suffix, act))
 }
 
+/// a state of a state machine, as recorded by an `sm_data` link (or, for the earlier
+/// states of a batch, by the link to its last state).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmHistoryEntry {
+    pub state_hh: HeaderHash,
+    pub state: FlatValue<Marker>,
+    /// the action expression which produced this state. `None` for the initial state.
+    pub action: Option<Expr>,
+    /// the agent who linked this state.
+    pub agent: AgentPubKey,
+    pub timestamp: Timestamp,
+    /// the `sm_data` link itself, as named by a snapshot's `SmSnapshotTag`.
+    pub link_hh: HeaderHash,
+}
+
+/// the `sm_data` links from `sm_data_path` (see `sm_data_link_num_states`), oldest
+/// first. links with equal timestamps are ordered by hash, so every agent sees the same
+/// order.
+fn sm_data_links(sm_data_path: String) -> ExternResult<Vec<Link>> {
+    let path_hash = Path::from(sm_data_path).path_entry_hash()?;
+    let mut links: Vec<Link> = get_links(path_hash, Some(LinkTag::new(SM_DATA_TAG)))?
+        .into_iter()
+        .filter(|lnk| sm_data_link_num_states(&lnk.tag).is_some())
+        .collect();
+    links.sort_by(|x, y| {
+        (x.timestamp, &x.create_link_hash).cmp(&(y.timestamp, &y.create_link_hash))
+    });
+    Ok(links)
+}
+
+/// the states which an `sm_data` link to `state_hh` stands for, oldest first: the
+/// unlinked states of its batch (if any), which are found by following each state's
+/// `sm_data` operand back, then `state_hh` itself. the walk stops short of `num_states`
+/// at any state which is not a step, such as an initial state.
+fn sm_linked_states(
+    sm_comp_hh: &HeaderHash,
+    state_hh: HeaderHash,
+    state_se: SensemakerEntry,
+    num_states: usize,
+) -> ExternResult<Vec<(HeaderHash, SensemakerEntry)>> {
+    let mut states = vec![(state_hh, state_se)];
+    while states.len() < num_states {
+        let prev_hh = match states.last().and_then(|(_hh, se)| sm_step(se, sm_comp_hh)) {
+            Some((sm_data_hh, _act_hh)) => sm_data_hh,
+            None => break,
+        };
+        let (_prev_eh, prev_se) = get_sensemaker_entry_by_headerhash(prev_hh.clone())?;
+        if sm_step(&prev_se, sm_comp_hh).is_none() {
+            break;
+        }
+        states.push((prev_hh, prev_se));
+    }
+    states.reverse();
+    Ok(states)
+}
+
+/// the state which `states` (as per `sm_linked_states`) were stepped from, and the
+/// action of each, in order. `None` if they are not steps.
+fn sm_batch_step(
+    states: &[(HeaderHash, SensemakerEntry)],
+    sm_comp_hh: &HeaderHash,
+) -> Option<(HeaderHash, Vec<HeaderHash>)> {
+    let (_first_hh, first_se) = states.first()?;
+    let (sm_data_hh, _act_hh) = sm_step(first_se, sm_comp_hh)?;
+    let act_hh_s = states
+        .iter()
+        .filter_map(|(_hh, se)| sm_step(se, sm_comp_hh))
+        .map(|(_sm_data_hh, act_hh)| act_hh)
+        .collect();
+    Some((sm_data_hh, act_hh_s))
+}
+
+/// the previous state & action of a state created by `step_sm_path`, which applies
+/// `sm_comp_hh` to them: `[sm_comp, sm_data, action]`. `None` for any other entry, such
+/// as an initial state (whatever its own shape).
+fn sm_step(
+    state_se: &SensemakerEntry,
+    sm_comp_hh: &HeaderHash,
+) -> Option<(HeaderHash, HeaderHash)> {
+    if state_se.operator != application_expr(3) {
+        return None;
+    }
+    let se_operand_hh = |operand: &SensemakerOperand| match operand {
+        SensemakerOperand::SensemakerOperand(hh) => Some(hh.clone()),
+        SensemakerOperand::OtherOperand(_) => None,
+    };
+    match &state_se.operands[..] {
+        [comp, sm_data, act] if se_operand_hh(comp).as_ref() == Some(sm_comp_hh) => {
+            Some((se_operand_hh(sm_data)?, se_operand_hh(act)?))
+        }
+        _ => None,
+    }
+}
+
+/// the action `Expr` of a state created by `step_sm_path`.
+fn sm_state_action(
+    state_se: &SensemakerEntry,
+    sm_comp_hh: &HeaderHash,
+) -> ExternResult<Option<Expr>> {
+    match sm_step(state_se, sm_comp_hh) {
+        Some((_sm_data_hh, act_hh)) => {
+            let (_act_eh, act_se) = get_sensemaker_entry_by_headerhash(act_hh)?;
+            Ok(Some(act_se.operator))
+        }
+        None => Ok(None),
+    }
+}
+
+/// a state linked at an `sm_data` path.
+struct SmEvent {
+    state_hh: HeaderHash,
+    state_se: SensemakerEntry,
+    /// the state which the link's states were stepped from, and their actions, as per
+    /// `sm_batch_step`.
+    step: Option<(HeaderHash, Vec<HeaderHash>)>,
+    /// the `sm_data` link to this state, and the number of links up to & including it.
+    /// `None` for a snapshot.
+    link: Option<(HeaderHash, usize)>,
+    /// for a snapshot, the state which it replaced. steps from that state count as
+    /// steps from the snapshot.
+    replaced_hh: Option<HeaderHash>,
+}
+
+/// the live states linked at `sm_data_path`, in link order, starting from its latest
+/// usable snapshot (if any, see `usable_sm_snapshot`). states linked up to that
+/// snapshot's cutoff link are not dereferenced, as the snapshot supersedes them.
+fn sm_events(sm_comp_hh: &HeaderHash, sm_data_path: String) -> ExternResult<Vec<SmEvent>> {
+    let mut events = Vec::new();
+    let links = sm_data_links(sm_data_path.clone())?;
+    let mut first_idx = 0;
+    if let Some(snapshot) = usable_sm_snapshot(sm_data_path, &links)? {
+        first_idx = snapshot.cutoff_idx + 1;
+        events.push(SmEvent {
+            state_hh: snapshot.snapshot_hh,
+            state_se: snapshot.snapshot_se,
+            step: None,
+            link: None,
+            replaced_hh: Some(snapshot.tag.state_hh),
+        });
+    }
+    for (idx, lnk) in links.into_iter().enumerate().skip(first_idx) {
+        let num_states = sm_data_link_num_states(&lnk.tag).unwrap_or(1);
+        let state_eh = match lnk.target.into_entry_hash() {
+            Some(eh) => eh,
+            None => continue,
+        };
+        let (state_hh, state_se) = match get_live_sensemaker_entry(state_eh)? {
+            Some(pair) => pair,
+            None => continue,
+        };
+        let mut states = sm_linked_states(sm_comp_hh, state_hh, state_se, num_states)?;
+        let step = sm_batch_step(&states, sm_comp_hh);
+        if let Some((state_hh, state_se)) = states.pop() {
+            events.push(SmEvent {
+                state_hh,
+                state_se,
+                step,
+                link: Some((lnk.create_link_hash, idx + 1)),
+                replaced_hh: None,
+            });
+        }
+    }
+    Ok(events)
+}
+
+/// a state of a state machine.
+struct SmState {
+    /// `None` for a state which no agent has committed, and which was only evaluated.
+    state_hh: Option<HeaderHash>,
+    scheme: Scheme,
+    flat_value: FlatValue<Marker>,
+}
+
+impl SmState {
+    fn committed(state_hh: HeaderHash, state_se: SensemakerEntry) -> Self {
+        SmState {
+            state_hh: Some(state_hh),
+            scheme: state_se.output_scheme,
+            flat_value: state_se.output_flat_value,
+        }
+    }
+}
+
+/// a linked state which `canonical_sm_state` took on as is, such that the canonical
+/// state just after its link was that state. it may therefore be snapshotted, with its
+/// link as the cutoff.
+struct SmAdopted {
+    state_hh: HeaderHash,
+    flat_value: FlatValue<Marker>,
+    link_hh: HeaderHash,
+    num_links: usize,
+}
+
+/// the canonical state of an `sm_data` path.
+struct SmFold {
+    state: SmState,
+    /// the number of states applied since the latest initial state or snapshot.
+    depth: usize,
+    /// the latest state which could be snapshotted, if any was linked since the latest
+    /// snapshot.
+    adopted: Option<SmAdopted>,
+}
+
+/// re-derive the canonical state at `sm_data_path` (or `None` if the path was never
+/// initialized).
+///
+/// concurrent `step_sm`s compute from the same previous state, so the latest link alone
+/// would drop all but one of their actions. instead, starting from the most recent
+/// initial state or snapshot, we apply the action(s) of every state linked since, in
+/// link order (which respects causality, up to clock skew). a state which was computed
+/// from the canonical state (via any unlinked states of its batch) is reused as is. the
+/// actions of any other state (i.e. one which forked) are re-applied to the canonical
+/// state, as per `apply_sm_action`. every agent which sees the same links derives the
+/// same value.
+fn canonical_sm_state(
+    sm_comp_hh: &HeaderHash,
+    sm_data_path: String,
+    commit: bool,
+) -> ExternResult<Option<SmFold>> {
+    let mut events = sm_events(sm_comp_hh, sm_data_path)?;
+    let root_idx = match events.iter().rposition(|ev| ev.step.is_none()) {
+        Some(idx) => idx,
+        None => return Ok(None),
+    };
+    let later_events = events.split_off(root_idx + 1);
+    let root = events.pop().expect("root_idx is in range");
+    let mut replaced_hh = root.replaced_hh.clone();
+    let mut adopted = sm_adopted(&root);
+    let mut current = SmState::committed(root.state_hh, root.state_se);
+    let mut depth = 0;
+    for ev in later_events {
+        let (sm_data_hh, act_hh_s) = match &ev.step {
+            Some(step) => step.clone(),
+            // `root_idx` is the last initial state or snapshot
+            None => continue,
+        };
+        depth += act_hh_s.len();
+        if Some(&sm_data_hh) == current.state_hh.as_ref()
+            || Some(&sm_data_hh) == replaced_hh.as_ref()
+        {
+            adopted = sm_adopted(&ev);
+            current = SmState::committed(ev.state_hh, ev.state_se);
+        } else {
+            for act_hh in act_hh_s {
+                debug!(
+                    "sm fork: re-applying {:?} onto {:?}",
+                    act_hh, current.state_hh
+                );
+                current = apply_sm_action(sm_comp_hh, current, act_hh, commit)?;
+            }
+        }
+        replaced_hh = None;
+    }
+    Ok(Some(SmFold {
+        state: current,
+        depth,
+        adopted,
+    }))
+}
+
+fn sm_adopted(ev: &SmEvent) -> Option<SmAdopted> {
+    ev.link.as_ref().map(|(link_hh, num_links)| SmAdopted {
+        state_hh: ev.state_hh.clone(),
+        flat_value: ev.state_se.output_flat_value.clone(),
+        link_hh: link_hh.clone(),
+        num_links: *num_links,
+    })
+}
+
+/// the canonical state's entry, on which a step builds.
+struct SmHead {
+    state_hh: HeaderHash,
+    depth: usize,
+}
+
+/// as `canonical_sm_state`, committing any state which it merges, but an uninitialized
+/// path is an error.
+fn get_sm_head(sm_comp_hh: &HeaderHash, sm_data_path: String) -> ExternResult<SmHead> {
+    match canonical_sm_state(sm_comp_hh, sm_data_path.clone(), true)? {
+        Some(SmFold {
+            state:
+                SmState {
+                    state_hh: Some(state_hh),
+                    ..
+                },
+            depth,
+        }) => Ok(SmHead { state_hh, depth }),
+        // with `commit`, every state is committed, so this path is uninitialized
+        _ => Err(SensemakerError::StateMachine {
+            path: sm_data_path,
+            tag: SM_DATA_TAG.into(),
+        }
+        .into()),
+    }
+}
+
+/// the `sm_comp` registered at `path_prefix`.
+fn get_sm_comp(path_prefix: String) -> ExternResult<(HeaderHash, SensemakerEntry)> {
+    match get_sensemaker_entry_by_path((path_prefix.clone(), SM_COMP_TAG.into()))? {
+        Some((sm_comp_eh, sm_comp_entry)) => {
+            let sm_comp_hh = util::get_hh(sm_comp_eh, GetOptions::content())?;
+            Ok((sm_comp_hh, sm_comp_entry))
+        }
+        None => Err(SensemakerError::StateMachine {
+            path: path_prefix,
+            tag: SM_COMP_TAG.into(),
+        }
+        .into()),
+    }
+}
+
+/// the state which results from applying `sm_comp` to `current` & `act_hh`. a state
+/// which some agent has already created is reused. otherwise, with `commit`, it is
+/// created, and without, it is only evaluated (as are any states which follow from it).
+fn apply_sm_action(
+    sm_comp_hh: &HeaderHash,
+    current: SmState,
+    act_hh: HeaderHash,
+    commit: bool,
+) -> ExternResult<SmState> {
+    if let Some(current_hh) = current.state_hh {
+        let application_se = mk_application_se(vec![sm_comp_hh.clone(), current_hh, act_hh])?;
+        if commit {
+            let (state_hh, _state_eh, state_se) = commit_sensemaker_entry(application_se)?;
+            return Ok(SmState::committed(state_hh, state_se));
+        }
+        let state_hh = get(hash_entry(&application_se)?, GetOptions::content())?
+            .map(|element| element.header_address().clone());
+        return Ok(SmState {
+            state_hh,
+            scheme: application_se.output_scheme,
+            flat_value: application_se.output_flat_value,
+        });
+    }
+    let sm_comp = resolve_operand(&SensemakerOperand::SensemakerOperand(sm_comp_hh.clone()))?;
+    let act = resolve_operand(&SensemakerOperand::SensemakerOperand(act_hh))?;
+    let (scheme, flat_value, _gas) = eval_application(
+        &application_expr(3),
+        vec![sm_comp, (current.scheme, current.flat_value), act],
+        sensemaker_properties()?.max_gas,
+    )?;
+    Ok(SmState {
+        state_hh: None,
+        scheme,
+        flat_value,
+    })
+}
+
+/// the canonical value at `path_prefix.path_suffix` (or `None` if it was never
+/// initialized), merging concurrent steps just as `step_sm_path` would. unlike the
+/// latest `sm_data` link, this reflects every step. nothing is committed.
+#[expand_remote_calls]
+pub fn get_sm_data_path(
+    (path_prefix, path_suffix): (String, String),
+) -> ExternResult<Option<FlatValue<Marker>>> {
+    let sm_data_path = compose_paths(&path_prefix, &path_suffix);
+    let (sm_comp_hh, _sm_comp_entry) = get_sm_comp(path_prefix)?;
+    let opt_fold = canonical_sm_state(&sm_comp_hh, sm_data_path, false)?;
+    Ok(opt_fold.map(|fold| fold.state.flat_value))
+}
+
+#[expand_remote_calls]
+pub fn get_sm_data(
+    (path_prefix, target_eh): (String, EntryHash),
+) -> ExternResult<Option<FlatValue<Marker>>> {
+    let target_eh_bytes: Vec<u8> = target_eh.into_inner();
+    let path_suffix = vec_u8_b64_encode(&target_eh_bytes);
+    get_sm_data_path((path_prefix, path_suffix))
+}
+
+/// the tag of a snapshot link: `SM_SNAPSHOT_TAG`, followed by this, serialized. the
+/// snapshot itself is a literal, which keeps none of the history behind the state, so it
+/// is the tag which records what the snapshot replaced.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SmSnapshotTag {
+    /// the state which was snapshotted.
+    pub state_hh: HeaderHash,
+    /// the `sm_data` link to `state_hh`. the snapshot supersedes the links up to & including
+    /// this one.
+    pub cutoff_link_hh: HeaderHash,
+    /// the number of `sm_data` links up to & including `cutoff_link_hh`, as the snapshot's
+    /// author saw them.
+    pub num_links: usize,
+}
+
+impl SmSnapshotTag {
+    pub fn to_link_tag(&self) -> ExternResult<LinkTag> {
+        let mut bytes = SM_SNAPSHOT_TAG.as_bytes().to_vec();
+        bytes.extend(holochain_serialized_bytes::encode(self).map_err(|err| {
+            SensemakerError::Other {
+                message: format!("could not encode SmSnapshotTag: {:?}", err),
+            }
+        })?);
+        Ok(LinkTag::new(bytes))
+    }
+
+    /// `None` if `tag` is not a well-formed snapshot link tag.
+    pub fn from_link_tag(tag: &LinkTag) -> Option<Self> {
+        let bytes = tag.0.strip_prefix(SM_SNAPSHOT_TAG.as_bytes())?;
+        holochain_serialized_bytes::decode(bytes).ok()
+    }
+}
+
+/// a snapshot of a state whose value is `flat_value`: that value as a literal, with no
+/// operands. `None` if the value has no literal form (see `flat_value_literal`), or if
+/// its literal would exceed the DNA's size limits.
+pub fn mk_sm_snapshot_se(flat_value: &FlatValue<Marker>) -> ExternResult<Option<SensemakerEntry>> {
+    let expr = match flat_value_literal(flat_value) {
+        Some(expr) => expr,
+        None => return Ok(None),
+    };
+    if input_limit_violation(&expr, 0)?.is_some() {
+        return Ok(None);
+    }
+    mk_sensemaker_entry(expr, vec![], None).map(Some)
+}
+
+/// an `Expr` which evaluates to `flat_value`, if it is built of `Int`s & lists.
+fn flat_value_literal(flat_value: &FlatValue<Marker>) -> Option<Expr> {
+    match &flat_value.0 {
+        Value::VInt(i) => Some(Expr::Lit(Lit::LInt(*i))),
+        Value::VNil => Some(Expr::Prim(PrimOp::Nil)),
+        Value::VCons(_, _) => {
+            // walk the spine iteratively, as lists may be long
+            let mut elems = Vec::new();
+            let mut rest = flat_value;
+            while let Value::VCons(hd, tl) = &rest.0 {
+                elems.push(flat_value_literal(hd)?);
+                rest = &**tl;
+            }
+            let add_cons = |acc, elem| app!(app!(Expr::Prim(PrimOp::Cons), elem), acc);
+            Some(
+                elems
+                    .into_iter()
+                    .rev()
+                    .fold(Expr::Prim(PrimOp::Nil), add_cons),
+            )
+        }
+        _ => None,
+    }
+}
+
+/// whether `snapshot_se` could replace `state_se`: it has no operands, and the same value
+/// & a unifying `Scheme`.
+pub fn is_sm_snapshot_of(snapshot_se: &SensemakerEntry, state_se: &SensemakerEntry) -> bool {
+    snapshot_se.operands.is_empty()
+        && snapshot_se.output_flat_value == state_se.output_flat_value
+        && check_expected_scheme(state_se.output_scheme.clone(), &snapshot_se.output_scheme).is_ok()
+}
+
+/// a snapshot from which to fold an `sm_data` path.
+struct SmSnapshot {
+    tag: SmSnapshotTag,
+    /// the position of `tag.cutoff_link_hh` among the path's `sm_data` links.
+    cutoff_idx: usize,
+    snapshot_hh: HeaderHash,
+    snapshot_se: SensemakerEntry,
+}
+
+/// the live snapshot at `sm_data_path` with the latest cutoff, among those whose author
+/// saw just the `links` (the path's `sm_data` links, in order) which we see up to that
+/// cutoff. any other snapshot would supersede a step which its author had not seen, so
+/// is passed over until a later snapshot accounts for that step.
+fn usable_sm_snapshot(sm_data_path: String, links: &[Link]) -> ExternResult<Option<SmSnapshot>> {
+    let path_hash = Path::from(sm_data_path).path_entry_hash()?;
+    let mut candidates = Vec::new();
+    for lnk in get_links(path_hash, Some(LinkTag::new(SM_SNAPSHOT_TAG)))? {
+        let (tag, snapshot_eh) = match (
+            SmSnapshotTag::from_link_tag(&lnk.tag),
+            lnk.target.into_entry_hash(),
+        ) {
+            (Some(tag), Some(eh)) => (tag, eh),
+            _ => continue,
+        };
+        let opt_cutoff_idx = links
+            .iter()
+            .position(|cutoff_lnk| cutoff_lnk.create_link_hash == tag.cutoff_link_hh);
+        match opt_cutoff_idx {
+            Some(cutoff_idx) if cutoff_idx + 1 == tag.num_links => {
+                candidates.push((cutoff_idx, tag, snapshot_eh))
+            }
+            _ => continue,
+        }
+    }
+    candidates.sort_by(|x, y| y.0.cmp(&x.0));
+    for (cutoff_idx, tag, snapshot_eh) in candidates {
+        if let Some((snapshot_hh, snapshot_se)) = get_live_sensemaker_entry(snapshot_eh)? {
+            return Ok(Some(SmSnapshot {
+                tag,
+                cutoff_idx,
+                snapshot_hh,
+                snapshot_se,
+            }));
+        }
+    }
+    Ok(None)
+}
+
+/// snapshot the latest adopted state at `sm_data_path` (see `SmAdopted`), & link the
+/// snapshot there. `None` if there is no such state, or it cannot be snapshotted.
+fn commit_sm_snapshot(
+    sm_comp_hh: &HeaderHash,
+    sm_data_path: String,
+) -> ExternResult<Option<HeaderHash>> {
+    let adopted = match canonical_sm_state(sm_comp_hh, sm_data_path.clone(), false)? {
+        Some(SmFold {
+            adopted: Some(adopted),
+            ..
+        }) => adopted,
+        _ => return Ok(None),
+    };
+    let snapshot_se = match mk_sm_snapshot_se(&adopted.flat_value)? {
+        Some(snapshot_se) => snapshot_se,
+        None => return Ok(None),
+    };
+    let (snapshot_hh, snapshot_eh, _snapshot_se) = commit_sensemaker_entry(snapshot_se)?;
+    let tag = SmSnapshotTag {
+        state_hh: adopted.state_hh,
+        cutoff_link_hh: adopted.link_hh,
+        num_links: adopted.num_links,
+    };
+    let path_hash = Path::from(sm_data_path).path_entry_hash()?;
+    create_link(path_hash, snapshot_eh, LinkType(0), tag.to_link_tag()?)?;
+    Ok(Some(snapshot_hh))
+}
+
+/// snapshot `sm_data_path` if `depth` states have been stepped since its last snapshot,
+/// & the DNA's `sm_snapshot_interval` calls for one.
+fn snapshot_sm_data_if_due(
+    sm_comp_hh: &HeaderHash,
+    sm_data_path: String,
+    depth: usize,
+) -> ExternResult<()> {
+    match sensemaker_properties()?.sm_snapshot_interval {
+        Some(interval) if interval > 0 && depth >= interval => {
+            commit_sm_snapshot(sm_comp_hh, sm_data_path)?;
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// snapshot the state at `path_prefix.path_suffix`, so that later steps need not look
+/// behind it. this is the latest state which was linked as is (rather than merged), so
+/// any steps which were merged after it are still folded. returns the snapshot's
+/// `HeaderHash`.
+#[expand_remote_calls]
+pub fn snapshot_sm_data_path(
+    (path_prefix, path_suffix): (String, String),
+) -> ExternResult<HeaderHash> {
+    let sm_data_path = compose_paths(&path_prefix, &path_suffix);
+    let (sm_comp_hh, _sm_comp_entry) = get_sm_comp(path_prefix)?;
+    match commit_sm_snapshot(&sm_comp_hh, sm_data_path.clone())? {
+        Some(snapshot_hh) => Ok(snapshot_hh),
+        None => Err(SensemakerError::InvalidInput {
+            message: format!(
+                "{}: no state since the latest snapshot which can be snapshotted",
+                sm_data_path
+            ),
+        }
+        .into()),
+    }
+}
+
+#[expand_remote_calls]
+pub fn snapshot_sm_data((path_prefix, target_eh): (String, EntryHash)) -> ExternResult<HeaderHash> {
+    let target_eh_bytes: Vec<u8> = target_eh.into_inner();
+    let path_suffix = vec_u8_b64_encode(&target_eh_bytes);
+    snapshot_sm_data_path((path_prefix, path_suffix))
+}
+
+/// every state which has been linked at `path_prefix.path_suffix` (including the unlinked
+/// states of each batch), oldest first, along with who produced it, when & by what
+/// action. deleted states are skipped.
+#[expand_remote_calls]
+pub fn get_sm_history_path(
+    (path_prefix, path_suffix): (String, String),
+) -> ExternResult<Vec<SmHistoryEntry>> {
+    let sm_data_path = compose_paths(&path_prefix, &path_suffix);
+    let (sm_comp_hh, _sm_comp_entry) = get_sm_comp(path_prefix)?;
+    let mut history = Vec::new();
+    for lnk in sm_data_links(sm_data_path)? {
+        let num_states = sm_data_link_num_states(&lnk.tag).unwrap_or(1);
+        let state_eh = match lnk.target.into_entry_hash() {
+            Some(eh) => eh,
+            None => continue,
+        };
+        let (state_hh, state_se) = match get_live_sensemaker_entry(state_eh)? {
+            Some(pair) => pair,
+            None => continue,
+        };
+        let link_element = get_element_by_headerhash(lnk.create_link_hash.clone())?;
+        for (state_hh, state_se) in sm_linked_states(&sm_comp_hh, state_hh, state_se, num_states)? {
+            history.push(SmHistoryEntry {
+                state_hh,
+                action: sm_state_action(&state_se, &sm_comp_hh)?,
+                state: state_se.output_flat_value,
+                agent: link_element.header().author().clone(),
+                timestamp: lnk.timestamp,
+                link_hh: lnk.create_link_hash.clone(),
+            });
+        }
+    }
+    Ok(history)
+}
+
+#[expand_remote_calls]
+pub fn get_sm_history(
+    (path_prefix, target_eh): (String, EntryHash),
+) -> ExternResult<Vec<SmHistoryEntry>> {
+    let target_eh_bytes: Vec<u8> = target_eh.into_inner();
+    let path_suffix = vec_u8_b64_encode(&target_eh_bytes);
+    get_sm_history_path((path_prefix, path_suffix))
+}
+
+/// the action type of a state machine, given the `Scheme` of its
+/// `sm_comp : data -> action -> data`.
+fn sm_action_scheme(sm_comp_sc: &Scheme) -> ExternResult<Scheme> {
+    let Scheme(tvs, ty) = sm_comp_sc;
+    if let Type::TArr(_data_ty, rest_ty) = ty {
+        if let Type::TArr(action_ty, _data_ty) = &**rest_ty {
+            let action_sc = Scheme(tvs.clone(), (**action_ty).clone());
+            return Ok(normalize(&mut InferState::new(), action_sc));
+        }
+    }
+    Err(SensemakerError::Type {
+        context: "sm_comp".into(),
+        message: format!("not of the form `data -> action -> data`: {:?}", ty),
+    }
+    .into())
+}
+
+/// parse the action `act`, and check that it unifies with `action_sc`.
+fn parse_sm_action(act: &str, action_sc: &Scheme) -> ExternResult<Expr> {
+    let act_expr = parse_expr(act)?;
+    let act_sc = infer_expr_with_is(&Env::new(), &mut InferState::new(), &act_expr).map_err(
+        |type_error| SensemakerError::Type {
+            context: "action".into(),
+            message: format!("{:?}", type_error),
+        },
+    )?;
+    check_expected_scheme(action_sc.clone(), &act_sc)?;
+    Ok(act_expr)
+}
+
+/// as `step_sm_path`, for many actions at once. every action is type-checked &
+/// applied (in order) before anything is committed. the states are committed as a
+/// chain, and only the last is linked, with an `SmBatchTag` from which
+/// `canonical_sm_state` & `get_sm_history` find the others.
+#[expand_remote_calls]
+pub fn step_sm_batch_path(
+    (path_prefix, path_suffix, acts): (String, String, Vec<String>),
+) -> ExternResult<()> {
+    let sm_data_path = compose_paths(&path_prefix, &path_suffix);
+
+    let (sm_comp_hh, sm_comp_entry) = get_sm_comp(path_prefix)?;
+    let action_sc = sm_action_scheme(&sm_comp_entry.output_scheme)?;
+
+    let mut act_exprs: Vec<Expr> = Vec::new();
+    for (idx, act) in acts.iter().enumerate() {
+        let act_expr =
+            parse_sm_action(act, &action_sc).map_err(|err| SensemakerError::BatchItem {
+                index: idx,
+                error: Box::new(err.into()),
+            })?;
+        act_exprs.push(act_expr);
+    }
+    if act_exprs.is_empty() {
+        return Ok(());
+    }
+
+    // fetch sm_data, merging any concurrent steps
+    let sm_head = get_sm_head(&sm_comp_hh, sm_data_path.clone())?;
+    let num_acts = act_exprs.len();
+
+    // each action, followed by the state which results from applying it.
+    let mut items: Vec<BatchSensemakerEntryInput> = Vec::new();
+    let mut sm_data =
+        BatchOperand::Existing(SensemakerOperand::SensemakerOperand(sm_head.state_hh));
+    for act_expr in act_exprs {
+        let act_idx = items.len();
+        items.push(BatchSensemakerEntryInput {
+            expr: act_expr,
+            args: vec![],
+        });
+        items.push(BatchSensemakerEntryInput {
+            expr: application_expr(3),
+            args: vec![
+                BatchOperand::Existing(SensemakerOperand::SensemakerOperand(sm_comp_hh.clone())),
+                sm_data,
+                BatchOperand::Index(act_idx),
+            ],
+        });
+        sm_data = BatchOperand::Index(act_idx + 1);
+    }
+    let hh_s = create_sensemaker_entries_batch(items)?;
+
+    // the last entry is the last state
+    if let Some(state_hh) = hh_s.last() {
+        let (state_eh, _state_se) = get_sensemaker_entry_by_headerhash(state_hh.clone())?;
+        link_sm_data(sm_data_path.clone(), state_eh, num_acts)?;
+    }
+    snapshot_sm_data_if_due(&sm_comp_hh, sm_data_path, sm_head.depth + num_acts)
+}
+
+#[expand_remote_calls]
+pub fn step_sm_batch(
+    (path_prefix, target_eh, acts): (String, EntryHash, Vec<String>),
+) -> ExternResult<()> {
+    let target_eh_bytes: Vec<u8> = target_eh.into_inner();
+    let path_suffix = vec_u8_b64_encode(&target_eh_bytes);
+    step_sm_batch_path((path_prefix, path_suffix, acts))
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+/// the canonical state of a target initialized under some path prefix.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmDataListing {
+    /// as passed to `initialize_sm_data_path`. for targets initialized by
+    /// `initialize_sm_data`, this is the base64 encoding of the target's `EntryHash`.
+    pub path_suffix: String,
+    /// `None` if the state merges concurrent steps, and no agent has yet committed it.
+    pub state_hh: Option<HeaderHash>,
+    pub state: FlatValue<Marker>,
+}
+
+/// the canonical `sm_data` (as per `get_sm_data_path`) of every target initialized under
+/// `path_prefix`.
+///
+/// if `opt_sort_by` is given, listings are sorted by their (`Int`) state. states of any
+/// other type come last, in no particular order. at most `opt_limit` listings are
+/// returned, after sorting.
+#[expand_remote_calls]
+pub fn list_sm_data(
+    (path_prefix, opt_sort_by, opt_limit): (String, Option<SortOrder>, Option<usize>),
+) -> ExternResult<Vec<SmDataListing>> {
+    let (sm_comp_hh, _sm_comp_entry) = get_sm_comp(path_prefix.clone())?;
+    let mut listings = Vec::new();
+    for target_path in Path::from(path_prefix.clone()).children_paths()? {
+        let path_suffix = match target_path.leaf() {
+            Some(component) => {
+                String::try_from(component).map_err(|err| SensemakerError::Other {
+                    message: format!("list_sm_data: path component: {:?}", err),
+                })?
+            }
+            None => continue,
+        };
+        let sm_data_path = compose_paths(&path_prefix, &path_suffix);
+        // paths under the prefix which were never initialized have no `sm_data`
+        if let Some(fold) = canonical_sm_state(&sm_comp_hh, sm_data_path, false)? {
+            listings.push(SmDataListing {
+                path_suffix,
+                state_hh: fold.state.state_hh,
+                state: fold.state.flat_value,
+            });
+        }
+    }
+
+    if let Some(sort_by) = opt_sort_by {
+        listings.sort_by(
+            |x, y| match (flat_value_int(&x.state), flat_value_int(&y.state)) {
+                (Some(x_int), Some(y_int)) => match sort_by {
+                    SortOrder::Ascending => x_int.cmp(&y_int),
+                    SortOrder::Descending => y_int.cmp(&x_int),
+                },
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => std::cmp::Ordering::Equal,
+            },
+        );
+    }
+    if let Some(limit) = opt_limit {
+        listings.truncate(limit);
+    }
+    Ok(listings)
+}
+
+fn flat_value_int(flat_value: &FlatValue<Marker>) -> Option<i64> {
+    match flat_value {
+        FlatValue(Value::VInt(i)) => Some(*i),
+        _ => None,
+    }
+}
+
 pub fn compose_entry_hash_path(path_string: &String, target_eh: EntryHash) -> String {
     let target_eh_bytes: Vec<u8> = target_eh.into_inner();
     format!("{}.{}", path_string, vec_u8_b64_encode(&target_eh_bytes))
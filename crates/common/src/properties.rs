@@ -0,0 +1,151 @@
+use hdk::prelude::*;
+
+use rep_lang_core::abstract_syntax::{Expr, Gas};
+use rep_lang_runtime::eval::FlatValue;
+
+use crate::{error::SensemakerError, Marker};
+
+/// DNA properties, set in `happs/social_sensemaker/dna.yaml`. any field which is left
+/// unset is unbounded.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, SerializedBytes)]
+pub struct SensemakerProperties {
+    /// the most gas which evaluating a single `SensemakerEntry` may consume.
+    #[serde(default)]
+    pub max_gas: Option<Gas>,
+    /// the most operands which a single `SensemakerEntry` may have. each operand costs a
+    /// `must_get_valid_element` during validation.
+    #[serde(default)]
+    pub max_operands: Option<usize>,
+    /// the most nodes which an operator `Expr` may have.
+    #[serde(default)]
+    pub max_expr_size: Option<usize>,
+    /// the deepest nesting which an operator `Expr` may have.
+    #[serde(default)]
+    pub max_expr_depth: Option<usize>,
+    /// the most bytes which an `output_flat_value` may occupy, once serialized.
+    #[serde(default)]
+    pub max_flat_value_bytes: Option<usize>,
+    /// the most bytes which an entry referenced by an `OtherOperand` may occupy. each
+    /// byte becomes an element of a (nested) list.
+    #[serde(default)]
+    pub max_other_operand_bytes: Option<usize>,
+    /// `step_sm` snapshots an `sm_data` path once this many states have been stepped
+    /// since its last snapshot (or initialization).
+    #[serde(default)]
+    pub sm_snapshot_interval: Option<usize>,
+}
+
+/// decode the `SensemakerProperties` of the current DNA. a DNA with `properties: null`
+/// gets the defaults.
+pub fn sensemaker_properties() -> ExternResult<SensemakerProperties> {
+    let properties = dna_info()?.properties;
+    let opt_props: Option<SensemakerProperties> =
+        holochain_serialized_bytes::decode(properties.bytes()).map_err(|err| {
+            SensemakerError::Other {
+                message: format!("could not decode DNA properties: {:?}", err),
+            }
+        })?;
+    Ok(opt_props.unwrap_or_default())
+}
+
+/// `Some(err)` if `gas` exceeds the `max_gas` budget. evaluation itself is bounded by
+/// passing `max_gas` to `eval_application`; this checks a gas count which was declared,
+/// e.g. an entry's `start_gas`, before anything is evaluated.
+pub fn gas_budget_violation(gas: Gas) -> ExternResult<Option<SensemakerError>> {
+    match sensemaker_properties()?.max_gas {
+        Some(max_gas) if gas > max_gas => Ok(Some(SensemakerError::GasBudgetExceeded {
+            used: gas,
+            max_gas,
+        })),
+        _ => Ok(None),
+    }
+}
+
+/// `Some(err)` if `expr` or `num_operands` exceed their limits. this is cheap, so should
+/// be checked before any operands are dereferenced.
+pub fn input_limit_violation(
+    expr: &Expr,
+    num_operands: usize,
+) -> ExternResult<Option<SensemakerError>> {
+    let props = sensemaker_properties()?;
+    if let Some(err) = limit_violation("max_operands", num_operands, props.max_operands) {
+        return Ok(Some(err));
+    }
+    let (size, depth) = expr_size_depth(expr);
+    if let Some(err) = limit_violation("max_expr_size", size, props.max_expr_size) {
+        return Ok(Some(err));
+    }
+    Ok(limit_violation(
+        "max_expr_depth",
+        depth,
+        props.max_expr_depth,
+    ))
+}
+
+/// `Some(err)` if `flat_value` serializes to more than `max_flat_value_bytes`.
+pub fn flat_value_limit_violation(
+    flat_value: &FlatValue<Marker>,
+) -> ExternResult<Option<SensemakerError>> {
+    match sensemaker_properties()?.max_flat_value_bytes {
+        None => Ok(None),
+        Some(max) => {
+            let bytes = holochain_serialized_bytes::encode(flat_value).map_err(|err| {
+                SensemakerError::Other {
+                    message: format!("could not encode FlatValue: {:?}", err),
+                }
+            })?;
+            Ok(limit_violation(
+                "max_flat_value_bytes",
+                bytes.len(),
+                Some(max),
+            ))
+        }
+    }
+}
+
+/// `Some(err)` if an `OtherOperand`'s entry, of `num_bytes`, is too large to expose as
+/// a list.
+pub fn other_operand_limit_violation(num_bytes: usize) -> ExternResult<Option<SensemakerError>> {
+    Ok(limit_violation(
+        "max_other_operand_bytes",
+        num_bytes,
+        sensemaker_properties()?.max_other_operand_bytes,
+    ))
+}
+
+fn limit_violation(limit: &str, value: usize, opt_max: Option<usize>) -> Option<SensemakerError> {
+    match opt_max {
+        Some(max) if value > max => Some(SensemakerError::LimitExceeded {
+            limit: limit.into(),
+            value,
+            max,
+        }),
+        _ => None,
+    }
+}
+
+/// the number of nodes in `expr`, and the depth of its deepest node. iterative, as
+/// the `Expr`s we are guarding against may be deep enough to overflow the stack.
+fn expr_size_depth(expr: &Expr) -> (usize, usize) {
+    let mut size = 0;
+    let mut max_depth = 0;
+    let mut stack: Vec<(&Expr, usize)> = vec![(expr, 1)];
+    while let Some((e, depth)) = stack.pop() {
+        size += 1;
+        max_depth = max_depth.max(depth);
+        match e {
+            Expr::Var(_) | Expr::Lit(_) | Expr::Prim(_) => {}
+            Expr::Lam(_, bd) | Expr::Fix(bd) => stack.push((bd, depth + 1)),
+            Expr::App(e1, e2) | Expr::Let(_, e1, e2) => {
+                stack.push((e1, depth + 1));
+                stack.push((e2, depth + 1));
+            }
+            Expr::If(b, t, f) => {
+                stack.push((b, depth + 1));
+                stack.push((t, depth + 1));
+                stack.push((f, depth + 1));
+            }
+        }
+    }
+    (size, max_depth)
+}
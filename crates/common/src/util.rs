@@ -1,5 +1,7 @@
 use hdk::prelude::*;
 
+use crate::error::SensemakerError;
+
 /// Tries to do a DHT get to retrieve data for the entry_hash,
 /// and if this get is successful and we get some element, tries
 /// to convert this element into a type T and return the result
@@ -9,10 +11,10 @@ pub fn try_get_and_convert<T: TryFrom<Entry>>(
 ) -> ExternResult<T> {
     match get(entry_hash.clone(), get_options)? {
         Some(element) => try_from_element(element),
-        None => Err(WasmError::Guest(format!(
-            "There is no element at the hash {}",
-            entry_hash
-        ))),
+        None => Err(SensemakerError::Dereference {
+            hash: entry_hash.to_string(),
+        }
+        .into()),
     }
 }
 
@@ -26,10 +28,10 @@ pub fn try_get_and_convert_with_hh<T: TryFrom<Entry>>(
             let v = try_from_element(element)?;
             Ok((v, hh))
         }
-        None => Err(WasmError::Guest(format!(
-            "There is no element at the hash {}",
-            entry_hash
-        ))),
+        None => Err(SensemakerError::Dereference {
+            hash: entry_hash.to_string(),
+        }
+        .into()),
     }
 }
 
@@ -39,10 +41,10 @@ pub fn get_hh(entry_hash: EntryHash, get_options: GetOptions) -> ExternResult<He
             let hh = element.header_address().clone();
             Ok(hh)
         }
-        None => Err(WasmError::Guest(format!(
-            "There is no element at the hash {}",
-            entry_hash
-        ))),
+        None => Err(SensemakerError::Dereference {
+            hash: entry_hash.to_string(),
+        }
+        .into()),
     }
 }
 
@@ -52,10 +54,10 @@ pub fn get_hh(entry_hash: EntryHash, get_options: GetOptions) -> ExternResult<He
 pub fn try_get_element(entry_hash: EntryHash, get_options: GetOptions) -> ExternResult<Element> {
     match get(entry_hash.clone(), get_options)? {
         Some(element) => Ok(element),
-        None => Err(WasmError::Guest(format!(
-            "There is no element at the hash {}",
-            entry_hash
-        ))),
+        None => Err(SensemakerError::Dereference {
+            hash: entry_hash.to_string(),
+        }
+        .into()),
     }
 }
 
@@ -65,15 +67,16 @@ pub fn try_get_element(entry_hash: EntryHash, get_options: GetOptions) -> Extern
 pub fn try_from_element<T: TryFrom<Entry>>(element: Element) -> ExternResult<T> {
     match element.entry() {
         element::ElementEntry::Present(entry) => T::try_from(entry.clone()).map_err(|_| {
-            WasmError::Guest(format!(
-                "Couldn't convert Element entry {:?} into data type {}",
-                entry,
-                std::any::type_name::<T>()
-            ))
+            SensemakerError::NonPresent {
+                hash: element.header_address().to_string(),
+                expected: std::any::type_name::<T>().into(),
+            }
+            .into()
         }),
-        _ => Err(WasmError::Guest(format!(
-            "Element {:?} does not have an entry",
-            element
-        ))),
+        _ => Err(SensemakerError::NonPresent {
+            hash: element.header_address().to_string(),
+            expected: std::any::type_name::<T>().into(),
+        }
+        .into()),
     }
 }
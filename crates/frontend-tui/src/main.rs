@@ -515,6 +515,7 @@ async fn main() -> Result<(), Box<dyn error::Error>> {
                         let input = CreateSensemakerEntryInput {
                             expr: ves.expr.clone(),
                             args,
+                            expected_scheme: None,
                         };
                         let se_hash = hc_info.create_sensemaker_entry(input).await;
                         app.log_hc_response(format!("create: se_hash: {:?}", se_hash));
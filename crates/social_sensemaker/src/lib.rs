@@ -1,16 +1,23 @@
-use hdk::prelude::*;
+use hdk::{entry::must_get_valid_element, prelude::*};
 
 use common::{
-    create_sensemaker_entry_full, mk_sensemaker_entry, CreateSensemakerEntryInput, SchemeEntry,
-    SchemeRoot, SensemakerEntry,
+    compute_sensemaker_entry, create_sensemaker_entry_full, delete_sensemaker_entry_full,
+    is_sm_snapshot_of, operands_limit_violation,
+    properties::{flat_value_limit_violation, gas_budget_violation, input_limit_violation},
+    scheme_bucket_key, sm_data_link_num_states, update_sensemaker_entry_full,
+    CreateSensemakerEntryInput, SchemeBucket, SchemeEntry, SchemeRoot, SensemakerEntry,
+    SmSnapshotTag,
 };
+use rep_lang_runtime::types::Scheme;
+use social_sensemaker_core::{DEPENDENT_TAG, OWNER_TAG, SCHEME_BUCKET_TAG, SM_SNAPSHOT_TAG};
 
 entry_defs![
     Path::entry_def(),
     PathEntry::entry_def(),
     SensemakerEntry::entry_def(),
     SchemeEntry::entry_def(),
-    SchemeRoot::entry_def()
+    SchemeRoot::entry_def(),
+    SchemeBucket::entry_def()
 ];
 
 #[hdk_extern]
@@ -21,8 +28,18 @@ pub fn init(_: ()) -> ExternResult<InitCallbackResult> {
         zome_info()?.name,
         "set_sensemaker_entry_parse_rl_expr".into(),
     ));
+    functions.insert((
+        zome_info()?.name,
+        "set_sensemaker_entry_parse_rl_expr_with_args".into(),
+    ));
+    functions.insert((zome_info()?.name, "register_state_machine".into()));
     functions.insert((zome_info()?.name, "initialize_sm_data".into()));
     functions.insert((zome_info()?.name, "step_sm".into()));
+    functions.insert((zome_info()?.name, "step_sm_batch".into()));
+    functions.insert((zome_info()?.name, "get_sm_data".into()));
+    functions.insert((zome_info()?.name, "get_sm_history".into()));
+    functions.insert((zome_info()?.name, "snapshot_sm_data".into()));
+    functions.insert((zome_info()?.name, "list_sm_data".into()));
 
     let grant = ZomeCallCapGrant {
         access: CapAccess::Unrestricted,
@@ -48,6 +65,30 @@ pub(crate) fn validate_update_entry_sensemaker_entry(
     validate_create_update_entry_sensemaker_entry(op)
 }
 
+#[hdk_extern]
+pub(crate) fn validate_delete_entry_sensemaker_entry(
+    op: Op,
+) -> ExternResult<ValidateCallbackResult> {
+    match op {
+        Op::RegisterDelete {
+            delete,
+            original_header,
+            original_entry: _,
+        } => {
+            if &delete.hashed.content.author == original_header.author() {
+                Ok(ValidateCallbackResult::Valid)
+            } else {
+                Ok(ValidateCallbackResult::Invalid(
+                    "only the author of a SensemakerEntry may delete it".into(),
+                ))
+            }
+        }
+        _ => Ok(ValidateCallbackResult::Invalid(
+            "Unexpected op: not RegisterDelete".into(),
+        )),
+    }
+}
+
 pub fn validate_create_update_entry_sensemaker_entry(
     op: Op,
 ) -> ExternResult<ValidateCallbackResult> {
@@ -77,7 +118,34 @@ pub fn validate_create_update_entry_sensemaker_entry(
         ))),
     }?;
 
-    let computed_se = mk_sensemaker_entry(se.operator, se.operands)?;
+    // an entry which admits to exceeding the gas budget, or which exceeds the size
+    // limits, need not be evaluated at all (nor its operands dereferenced).
+    if let Some(err) = gas_budget_violation(se.start_gas)? {
+        return Ok(ValidateCallbackResult::Invalid(err.to_string()));
+    }
+    if let Some(err) = input_limit_violation(&se.operator, se.operands.len())? {
+        return Ok(ValidateCallbackResult::Invalid(err.to_string()));
+    }
+    if let Some(err) = flat_value_limit_violation(&se.output_flat_value)? {
+        return Ok(ValidateCallbackResult::Invalid(err.to_string()));
+    }
+    if let Some(err) = operands_limit_violation(&se.operands)? {
+        return Ok(ValidateCallbackResult::Invalid(err.to_string()));
+    }
+
+    // `start_gas` is within the budget, and an honest entry needs no more than that, so
+    // evaluation can give up as soon as it is exceeded.
+    let computed_se = match compute_sensemaker_entry(se.operator, se.operands, se.start_gas)? {
+        Ok(computed_se) => computed_se,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(format!(
+                "SensemakerEntry gas mismatch:\
+        \ncomputed: more than {}\
+        \nreceived: {}",
+                se.start_gas, se.start_gas
+            )))
+        }
+    };
 
     if computed_se.output_scheme != se.output_scheme {
         return Ok(ValidateCallbackResult::Invalid(format!(
@@ -97,14 +165,391 @@ pub fn validate_create_update_entry_sensemaker_entry(
         )));
     }
 
+    if computed_se.start_gas != se.start_gas {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "SensemakerEntry gas mismatch:\
+        \ncomputed: {}\
+        \nreceived: {}",
+            computed_se.start_gas, se.start_gas
+        )));
+    }
+
     Ok(ValidateCallbackResult::Valid)
 }
 
+#[hdk_extern]
+pub(crate) fn validate_create_entry_scheme_root(op: Op) -> ExternResult<ValidateCallbackResult> {
+    match op {
+        Op::StoreEntry { entry, header: _ } => match entry_as::<SchemeRoot>(&entry) {
+            Some(SchemeRoot) => Ok(ValidateCallbackResult::Valid),
+            None => Ok(ValidateCallbackResult::Invalid(
+                "Couldn't convert Entry into SchemeRoot".into(),
+            )),
+        },
+        _ => Ok(ValidateCallbackResult::Invalid(
+            "Unexpected op: not StoreEntry".into(),
+        )),
+    }
+}
+
+#[hdk_extern]
+pub(crate) fn validate_create_entry_scheme_entry(op: Op) -> ExternResult<ValidateCallbackResult> {
+    let scheme_entry: SchemeEntry = match op {
+        Op::StoreEntry { entry, header: _ } => match entry_as(&entry) {
+            Some(scheme_entry) => scheme_entry,
+            None => {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Couldn't convert Entry into SchemeEntry".into(),
+                ))
+            }
+        },
+        _ => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Unexpected op: not StoreEntry".into(),
+            ))
+        }
+    };
+    let Scheme(tvs, _ty) = &scheme_entry.sc;
+    if tvs
+        .iter()
+        .enumerate()
+        .any(|(idx, tv)| tvs[..idx].contains(tv))
+    {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "SchemeEntry quantifies a type variable more than once: {:?}",
+            scheme_entry.sc
+        )));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+// the Scheme index is shared by all agents, so none may replace or retract its entries.
+
+#[hdk_extern]
+pub(crate) fn validate_update_entry_scheme_root(_op: Op) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(
+        "SchemeRoot cannot be updated".into(),
+    ))
+}
+
+#[hdk_extern]
+pub(crate) fn validate_delete_entry_scheme_root(_op: Op) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(
+        "SchemeRoot cannot be deleted".into(),
+    ))
+}
+
+#[hdk_extern]
+pub(crate) fn validate_update_entry_scheme_entry(_op: Op) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(
+        "SchemeEntry cannot be updated".into(),
+    ))
+}
+
+#[hdk_extern]
+pub(crate) fn validate_delete_entry_scheme_entry(_op: Op) -> ExternResult<ValidateCallbackResult> {
+    Ok(ValidateCallbackResult::Invalid(
+        "SchemeEntry cannot be deleted".into(),
+    ))
+}
+
+/// validates the hops of the Scheme index (see `common::commit_sensemaker_entry`):
+///
+/// - `SchemeRoot` -> `SchemeBucket`, tagged `SCHEME_BUCKET_TAG`
+/// - `SchemeBucket` -> `SchemeEntry` (whose key must match), tagged `OWNER_TAG`
+/// - `SchemeEntry` -> `SensemakerEntry` (whose `output_scheme` must match), tagged
+///   `OWNER_TAG`
+///
+/// these are the only links which may be made from an index entry, whatever their tag,
+/// since `get_links` would return a link with a longer tag alongside them. new
+/// `SchemeRoot` -> `SchemeEntry` links, which predate the buckets, are rejected.
+/// reverse-dependency & state machine snapshot links are checked by
+/// `validate_dependent_link` & `validate_sm_snapshot_link`.
+#[hdk_extern]
+pub(crate) fn validate_create_link(op: Op) -> ExternResult<ValidateCallbackResult> {
+    let create_link = match op {
+        Op::RegisterCreateLink { create_link } => create_link.hashed.content,
+        _ => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Unexpected op: not RegisterCreateLink".into(),
+            ))
+        }
+    };
+
+    if let Some(base_eh) = create_link.base_address.clone().into_entry_hash() {
+        if base_eh == hash_entry(SchemeRoot)? {
+            return validate_scheme_root_link(create_link);
+        }
+        let base_entry = must_get_entry(base_eh)?.into_content();
+        if let Some(scheme_bucket) = entry_as::<SchemeBucket>(&base_entry) {
+            return validate_scheme_bucket_link(create_link, scheme_bucket);
+        }
+        if let Some(scheme_entry) = entry_as::<SchemeEntry>(&base_entry) {
+            return validate_scheme_entry_link(create_link, scheme_entry);
+        }
+    }
+
+    if create_link.tag.0.starts_with(SM_SNAPSHOT_TAG.as_bytes()) {
+        return validate_sm_snapshot_link(create_link);
+    }
+    if create_link.tag == LinkTag::new(DEPENDENT_TAG) {
+        return validate_dependent_link(create_link);
+    }
+    if create_link.tag == LinkTag::new(SCHEME_BUCKET_TAG) {
+        return Ok(ValidateCallbackResult::Invalid(
+            "SchemeBucket links must have SchemeRoot as their base".into(),
+        ));
+    }
+    Ok(ValidateCallbackResult::Valid)
+}
+
+/// the target of a link from the Scheme index, as an `A`. `Err` holds the `Invalid`
+/// message if the link is not tagged `tag`, or does not target an `A`.
+fn scheme_index_target<A: TryFrom<SerializedBytes, Error = SerializedBytesError>>(
+    create_link: CreateLink,
+    tag: &str,
+    message: &str,
+) -> ExternResult<Result<A, String>> {
+    if create_link.tag != LinkTag::new(tag) {
+        return Ok(Err(message.into()));
+    }
+    match create_link.target_address.into_entry_hash() {
+        Some(target_eh) => match entry_as::<A>(&must_get_entry(target_eh)?.into_content()) {
+            Some(target) => Ok(Ok(target)),
+            None => Ok(Err(message.into())),
+        },
+        None => Ok(Err(message.into())),
+    }
+}
+
+fn validate_scheme_root_link(create_link: CreateLink) -> ExternResult<ValidateCallbackResult> {
+    match scheme_index_target::<SchemeBucket>(
+        create_link,
+        SCHEME_BUCKET_TAG,
+        "SchemeRoot may only link to a SchemeBucket",
+    )? {
+        Ok(_) => Ok(ValidateCallbackResult::Valid),
+        Err(message) => Ok(ValidateCallbackResult::Invalid(message)),
+    }
+}
+
+fn validate_scheme_bucket_link(
+    create_link: CreateLink,
+    scheme_bucket: SchemeBucket,
+) -> ExternResult<ValidateCallbackResult> {
+    match scheme_index_target::<SchemeEntry>(
+        create_link,
+        OWNER_TAG,
+        "SchemeBucket may only link to a SchemeEntry",
+    )? {
+        Ok(scheme_entry) if scheme_bucket_key(&scheme_entry.sc) == scheme_bucket.key => {
+            Ok(ValidateCallbackResult::Valid)
+        }
+        Ok(scheme_entry) => Ok(ValidateCallbackResult::Invalid(format!(
+            "SchemeEntry {:?} does not belong in SchemeBucket {:?}",
+            scheme_entry.sc, scheme_bucket.key
+        ))),
+        Err(message) => Ok(ValidateCallbackResult::Invalid(message)),
+    }
+}
+
+fn validate_scheme_entry_link(
+    create_link: CreateLink,
+    scheme_entry: SchemeEntry,
+) -> ExternResult<ValidateCallbackResult> {
+    match scheme_index_target::<SensemakerEntry>(
+        create_link,
+        OWNER_TAG,
+        "SchemeEntry may only link to a SensemakerEntry",
+    )? {
+        Ok(se) if se.output_scheme == scheme_entry.sc => Ok(ValidateCallbackResult::Valid),
+        Ok(se) => Ok(ValidateCallbackResult::Invalid(format!(
+            "SensemakerEntry scheme mismatch:\
+        \nSchemeEntry: {:?}\
+        \nSensemakerEntry: {:?}",
+            scheme_entry.sc, se.output_scheme
+        ))),
+        Err(message) => Ok(ValidateCallbackResult::Invalid(message)),
+    }
+}
+
+/// a reverse-dependency link must target a `SensemakerEntry` which lists its base among
+/// its operands.
+fn validate_dependent_link(create_link: CreateLink) -> ExternResult<ValidateCallbackResult> {
+    let (operand_hh, dependent_hh) = match (
+        create_link.base_address.into_header_hash(),
+        create_link.target_address.into_header_hash(),
+    ) {
+        (Some(operand_hh), Some(dependent_hh)) => (operand_hh, dependent_hh),
+        _ => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "dependent links must be between headers".into(),
+            ))
+        }
+    };
+    let dependent_element = must_get_valid_element(dependent_hh)?;
+    let opt_dependent_se = dependent_element
+        .entry()
+        .as_option()
+        .and_then(entry_as::<SensemakerEntry>);
+    match opt_dependent_se {
+        Some(dependent_se)
+            if dependent_se
+                .operands
+                .iter()
+                .any(|operand| operand.header_hash() == &operand_hh) =>
+        {
+            Ok(ValidateCallbackResult::Valid)
+        }
+        Some(_) => Ok(ValidateCallbackResult::Invalid(
+            "dependent link target does not have its base as an operand".into(),
+        )),
+        None => Ok(ValidateCallbackResult::Invalid(
+            "dependent links must target a SensemakerEntry".into(),
+        )),
+    }
+}
+
+/// a snapshot link must target a `SensemakerEntry` which could replace the state named
+/// by its `SmSnapshotTag`, and that state must have been linked at the same path, by the
+/// `sm_data` link which the tag names as its cutoff.
+fn validate_sm_snapshot_link(create_link: CreateLink) -> ExternResult<ValidateCallbackResult> {
+    let snapshot_tag = match SmSnapshotTag::from_link_tag(&create_link.tag) {
+        Some(snapshot_tag) => snapshot_tag,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "malformed sm_snapshot link tag".into(),
+            ))
+        }
+    };
+    let opt_snapshot_se = match create_link.target_address.into_entry_hash() {
+        Some(target_eh) => entry_as::<SensemakerEntry>(&must_get_entry(target_eh)?.into_content()),
+        None => None,
+    };
+    let snapshot_se = match opt_snapshot_se {
+        Some(snapshot_se) => snapshot_se,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "sm_snapshot links must target a SensemakerEntry".into(),
+            ))
+        }
+    };
+
+    let state_element = must_get_valid_element(snapshot_tag.state_hh)?;
+    let is_cutoff_link = match must_get_valid_element(snapshot_tag.cutoff_link_hh)?.header() {
+        Header::CreateLink(cutoff_link) => {
+            cutoff_link.base_address == create_link.base_address
+                && sm_data_link_num_states(&cutoff_link.tag).is_some()
+                && cutoff_link
+                    .target_address
+                    .clone()
+                    .into_entry_hash()
+                    .as_ref()
+                    == state_element.header().entry_hash()
+        }
+        _ => false,
+    };
+    if !is_cutoff_link {
+        return Ok(ValidateCallbackResult::Invalid(
+            "sm_snapshot cutoff must be an sm_data link to the state, at the same path".into(),
+        ));
+    }
+
+    let opt_state_se = state_element
+        .entry()
+        .as_option()
+        .and_then(entry_as::<SensemakerEntry>);
+    match opt_state_se {
+        Some(state_se) if is_sm_snapshot_of(&snapshot_se, &state_se) => {
+            Ok(ValidateCallbackResult::Valid)
+        }
+        _ => Ok(ValidateCallbackResult::Invalid(
+            "sm_snapshot target is not a literal of the snapshotted state".into(),
+        )),
+    }
+}
+
+/// links (the Scheme index & state machine paths included) may only be deleted by
+/// their author.
+#[hdk_extern]
+pub(crate) fn validate_delete_link(op: Op) -> ExternResult<ValidateCallbackResult> {
+    match op {
+        Op::RegisterDeleteLink {
+            delete_link,
+            create_link,
+        } => {
+            if delete_link.hashed.content.author == create_link.author {
+                Ok(ValidateCallbackResult::Valid)
+            } else {
+                Ok(ValidateCallbackResult::Invalid(
+                    "only the author of a link may delete it".into(),
+                ))
+            }
+        }
+        _ => Ok(ValidateCallbackResult::Invalid(
+            "Unexpected op: not RegisterDeleteLink".into(),
+        )),
+    }
+}
+
 #[hdk_extern]
 pub fn create_sensemaker_entry(input: CreateSensemakerEntryInput) -> ExternResult<HeaderHash> {
     create_sensemaker_entry_full(input).map(|t| t.0)
 }
 
+/// commits `se` exactly as given, without computing it or maintaining any indices.
+/// validation still applies; this exists only to exercise it.
+#[cfg(feature = "test_utils")]
+#[hdk_extern]
+pub fn create_sensemaker_entry_raw(se: SensemakerEntry) -> ExternResult<HeaderHash> {
+    create_entry(&se)
+}
+
+/// links `target_eh` from `path_string` exactly as given, bypassing the functions which
+/// would usually create such a link. validation still applies; this exists only to
+/// exercise it.
+#[cfg(feature = "test_utils")]
+#[hdk_extern]
+pub fn create_link_raw(
+    (path_string, target_eh, tag): (String, EntryHash, LinkTag),
+) -> ExternResult<HeaderHash> {
+    let path = Path::try_from(path_string)?;
+    path.ensure()?;
+    create_link(path.path_entry_hash()?, target_eh, LinkType(0), tag)
+}
+
+/// links `target_eh` from the `SchemeEntry` of `sc` exactly as given, bypassing the Scheme
+/// index maintenance. validation still applies; this exists only to exercise it.
+#[cfg(feature = "test_utils")]
+#[hdk_extern]
+pub fn create_scheme_entry_link_raw(
+    (sc, target_eh, tag): (Scheme, EntryHash, LinkTag),
+) -> ExternResult<HeaderHash> {
+    create_link(
+        hash_entry(&SchemeEntry { sc })?,
+        target_eh,
+        LinkType(0),
+        tag,
+    )
+}
+
+#[hdk_extern]
+pub fn update_sensemaker_entry(
+    (original_hh, input): (HeaderHash, CreateSensemakerEntryInput),
+) -> ExternResult<HeaderHash> {
+    update_sensemaker_entry_full(original_hh, input).map(|t| t.0)
+}
+
+#[hdk_extern]
+pub fn delete_sensemaker_entry(hh: HeaderHash) -> ExternResult<HeaderHash> {
+    delete_sensemaker_entry_full(hh)
+}
+
+/// `entry` as an `A`, if it is one.
+fn entry_as<A: TryFrom<SerializedBytes, Error = SerializedBytesError>>(entry: &Entry) -> Option<A> {
+    entry_to_struct(entry).ok().flatten()
+}
+
 pub fn entry_to_struct<A: TryFrom<SerializedBytes, Error = SerializedBytesError>>(
     entry: &Entry,
 ) -> Result<Option<A>, SerializedBytesError> {
@@ -45,6 +45,7 @@ pub async fn test_creation_retrieval_se() -> anyhow::Result<()> {
     let csei = CreateSensemakerEntryInput {
         expr: expr.clone(),
         args: vec![],
+        expected_scheme: None,
     };
     let hh: HeaderHash = conductors[0]
         .call(&alice.zome(ZOME_NAME), "create_sensemaker_entry", csei)
@@ -102,6 +103,7 @@ pub async fn test_round_robin_incrementation() -> anyhow::Result<()> {
     let init_csei = CreateSensemakerEntryInput {
         expr: Expr::Lit(Lit::LInt(0)),
         args: vec![],
+        expected_scheme: None,
     };
     let hh: HeaderHash = conductors[0]
         .call(
@@ -119,6 +121,7 @@ pub async fn test_round_robin_incrementation() -> anyhow::Result<()> {
         let csei = CreateSensemakerEntryInput {
             expr: app!(Expr::Prim(PrimOp::Add), Expr::Lit(Lit::LInt(1))),
             args: vec![SensemakerOperand::SensemakerOperand(last_se_hh)],
+            expected_scheme: None,
         };
 
         let new_hh: HeaderHash = conductors[idx % NUM_CONDUCTORS]
@@ -173,6 +176,7 @@ pub async fn test_round_robin_fibonacci() -> anyhow::Result<()> {
         let init_csei = CreateSensemakerEntryInput {
             expr: Expr::Lit(Lit::LInt(0)),
             args: vec![],
+            expected_scheme: None,
         };
         let init_hh: HeaderHash = conductors[0]
             .call(
@@ -187,6 +191,7 @@ pub async fn test_round_robin_fibonacci() -> anyhow::Result<()> {
         let init_csei = CreateSensemakerEntryInput {
             expr: Expr::Lit(Lit::LInt(1)),
             args: vec![],
+            expected_scheme: None,
         };
         let init_hh: HeaderHash = conductors[0]
             .call(
@@ -208,6 +213,7 @@ pub async fn test_round_robin_fibonacci() -> anyhow::Result<()> {
                 SensemakerOperand::SensemakerOperand(hh_0.clone()),
                 SensemakerOperand::SensemakerOperand(hh_1.clone()),
             ],
+            expected_scheme: None,
         };
 
         let new_hh: HeaderHash = conductors[idx % NUM_CONDUCTORS]
@@ -259,6 +265,7 @@ pub async fn test_round_robin_arity_n_sum() -> anyhow::Result<()> {
     let init_csei = CreateSensemakerEntryInput {
         expr: Expr::Lit(Lit::LInt(1)),
         args: vec![],
+        expected_scheme: None,
     };
     let hh: HeaderHash = conductors[0]
         .call(
@@ -298,6 +305,7 @@ pub async fn test_round_robin_arity_n_sum() -> anyhow::Result<()> {
                 .cloned()
                 .map(SensemakerOperand::SensemakerOperand)
                 .collect(),
+            expected_scheme: None,
         };
 
         let new_hh: HeaderHash = conductors[idx % NUM_CONDUCTORS]
@@ -330,6 +338,1742 @@ pub async fn test_round_robin_arity_n_sum() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_se_provenance() -> anyhow::Result<()> {
+    use holochain::test_utils::consistency_10s;
+
+    use common::{CreateSensemakerEntryInput, ProvenanceNode, SeProvenance, SensemakerOperand};
+    use rep_lang_core::{
+        abstract_syntax::{Expr, Lit, PrimOp},
+        app,
+    };
+
+    const NUM_CONDUCTORS: usize = 2;
+
+    let (conductors, apps) = setup_conductors_cells(NUM_CONDUCTORS).await;
+    let cells = apps.cells_flattened();
+
+    let init_csei = CreateSensemakerEntryInput {
+        expr: Expr::Lit(Lit::LInt(0)),
+        args: vec![],
+        expected_scheme: None,
+    };
+    let init_hh: HeaderHash = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "create_sensemaker_entry",
+            init_csei,
+        )
+        .await;
+
+    consistency_10s(&cells).await;
+
+    let incr_csei = CreateSensemakerEntryInput {
+        expr: app!(Expr::Prim(PrimOp::Add), Expr::Lit(Lit::LInt(1))),
+        args: vec![SensemakerOperand::SensemakerOperand(init_hh.clone())],
+        expected_scheme: None,
+    };
+    let incr_hh: HeaderHash = conductors[1]
+        .call(
+            &cells[1].zome(ZOME_NAME),
+            "create_sensemaker_entry",
+            incr_csei,
+        )
+        .await;
+
+    consistency_10s(&cells).await;
+
+    let provenance: SeProvenance = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "get_se_provenance",
+            (incr_hh.clone(), 8_usize),
+        )
+        .await;
+    assert_eq!(provenance.nodes.len(), 2);
+    assert_eq!(provenance.edges.len(), 1);
+    assert_eq!(provenance.edges[0].consumer, incr_hh);
+    assert_eq!(provenance.edges[0].operand, init_hh);
+    match &provenance.nodes[1] {
+        ProvenanceNode::Sensemaker { hh, author, .. } => {
+            assert_eq!(hh, &init_hh);
+            assert_eq!(author, cells[0].agent_pubkey());
+        }
+        node => panic!("unexpected provenance node: {:?}", node),
+    }
+
+    // a depth of 0 yields only the root
+    let shallow_provenance: SeProvenance = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "get_se_provenance",
+            (incr_hh, 0_usize),
+        )
+        .await;
+    assert_eq!(shallow_provenance.nodes.len(), 1);
+    assert!(shallow_provenance.edges.is_empty());
+
+    Ok(())
+}
+
+/// build a small DAG in one zome call: `x = 1`, `y = x + 1`, `z = x + y`.
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_batch_creation() -> anyhow::Result<()> {
+    use holochain::test_utils::consistency_10s;
+
+    use common::{BatchOperand, BatchSensemakerEntryInput, SensemakerEntry, SensemakerOperand};
+    use rep_lang_core::{
+        abstract_syntax::{Expr, Lit, PrimOp},
+        app,
+    };
+    use rep_lang_runtime::eval::{FlatValue, Value};
+
+    const NUM_CONDUCTORS: usize = 2;
+
+    let (conductors, apps) = setup_conductors_cells(NUM_CONDUCTORS).await;
+    let cells = apps.cells_flattened();
+
+    let items = vec![
+        BatchSensemakerEntryInput {
+            expr: Expr::Lit(Lit::LInt(1)),
+            args: vec![],
+        },
+        BatchSensemakerEntryInput {
+            expr: app!(Expr::Prim(PrimOp::Add), Expr::Lit(Lit::LInt(1))),
+            args: vec![BatchOperand::Index(0)],
+        },
+        BatchSensemakerEntryInput {
+            expr: Expr::Prim(PrimOp::Add),
+            args: vec![BatchOperand::Index(0), BatchOperand::Index(1)],
+        },
+    ];
+    let hh_s: Vec<HeaderHash> = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "create_sensemaker_entries_batch",
+            items,
+        )
+        .await;
+    assert_eq!(hh_s.len(), 3);
+
+    consistency_10s(&cells).await;
+
+    let (_se_hash, se): (EntryHash, SensemakerEntry) = conductors[1]
+        .call(
+            &cells[1].zome(ZOME_NAME),
+            "get_sensemaker_entry_by_headerhash",
+            hh_s[2].clone(),
+        )
+        .await;
+    assert_eq!(se.output_flat_value, FlatValue(Value::VInt(3)));
+    match &se.operands[..] {
+        [SensemakerOperand::SensemakerOperand(x_hh), SensemakerOperand::SensemakerOperand(y_hh)] => {
+            assert_eq!(x_hh, &hh_s[0]);
+            assert_eq!(y_hh, &hh_s[1]);
+        }
+        operands => panic!("unexpected operands: {:?}", operands),
+    }
+
+    // a forward reference fails the whole batch
+    let bad_items = vec![BatchSensemakerEntryInput {
+        expr: app!(Expr::Prim(PrimOp::Add), Expr::Lit(Lit::LInt(1))),
+        args: vec![BatchOperand::Index(0)],
+    }];
+    let res: Result<Vec<HeaderHash>, _> = conductors[0]
+        .call_fallible(
+            &cells[0].zome(ZOME_NAME),
+            "create_sensemaker_entries_batch",
+            bad_items,
+        )
+        .await;
+    assert!(res.is_err());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_bundle_export_import() -> anyhow::Result<()> {
+    use holochain::test_utils::consistency_10s;
+
+    use common::{
+        bundle::SensemakerBundle, BatchOperand, BatchSensemakerEntryInput, SensemakerEntry,
+    };
+    use rep_lang_core::{
+        abstract_syntax::{Expr, Lit, PrimOp},
+        app,
+    };
+    use rep_lang_runtime::eval::{FlatValue, Value};
+
+    const NUM_CONDUCTORS: usize = 2;
+
+    let (conductors, apps) = setup_conductors_cells(NUM_CONDUCTORS).await;
+    let cells = apps.cells_flattened();
+
+    let items = vec![
+        BatchSensemakerEntryInput {
+            expr: Expr::Lit(Lit::LInt(2)),
+            args: vec![],
+        },
+        BatchSensemakerEntryInput {
+            expr: app!(Expr::Prim(PrimOp::Add), Expr::Lit(Lit::LInt(3))),
+            args: vec![BatchOperand::Index(0)],
+        },
+    ];
+    let hh_s: Vec<HeaderHash> = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "create_sensemaker_entries_batch",
+            items,
+        )
+        .await;
+
+    let bundle: SensemakerBundle = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "export_sensemaker_bundle",
+            vec![hh_s[1].clone()],
+        )
+        .await;
+    assert_eq!(bundle.nodes.len(), 2);
+    assert_eq!(bundle.nodes[0].origin_hh, hh_s[0]);
+    assert_eq!(bundle.nodes[1].origin_hh, hh_s[1]);
+
+    let cli_str = bundle.to_cli_format().unwrap();
+    let bundle = SensemakerBundle::from_cli_format(&cli_str).unwrap();
+
+    let imported_hh_s: Vec<HeaderHash> = conductors[1]
+        .call(
+            &cells[1].zome(ZOME_NAME),
+            "import_sensemaker_bundle",
+            bundle,
+        )
+        .await;
+    assert_eq!(imported_hh_s.len(), 2);
+
+    consistency_10s(&cells).await;
+
+    let (_se_hash, se): (EntryHash, SensemakerEntry) = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "get_sensemaker_entry_by_headerhash",
+            imported_hh_s[1].clone(),
+        )
+        .await;
+    assert_eq!(se.output_flat_value, FlatValue(Value::VInt(5)));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_expected_scheme() -> anyhow::Result<()> {
+    use common::{CreateSensemakerEntryInput, SensemakerEntry};
+    use rep_lang_core::{
+        abstract_syntax::{Expr, Lit, Name},
+        lam,
+    };
+
+    let (conductors, apps) = setup_conductors_cells(1).await;
+    let cells = apps.cells_flattened();
+
+    let int_csei = CreateSensemakerEntryInput {
+        expr: Expr::Lit(Lit::LInt(0)),
+        args: vec![],
+        expected_scheme: None,
+    };
+    let hh: HeaderHash = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "create_sensemaker_entry",
+            int_csei,
+        )
+        .await;
+    let (_se_hash, int_se): (EntryHash, SensemakerEntry) = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "get_sensemaker_entry_by_headerhash",
+            hh,
+        )
+        .await;
+
+    // an `Int` unifies with an expected `Int`
+    let ok_csei = CreateSensemakerEntryInput {
+        expr: Expr::Lit(Lit::LInt(1)),
+        args: vec![],
+        expected_scheme: Some(int_se.output_scheme.clone()),
+    };
+    let _hh: HeaderHash = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "create_sensemaker_entry",
+            ok_csei,
+        )
+        .await;
+
+    // a function does not
+    let bad_csei = CreateSensemakerEntryInput {
+        expr: lam!(Name("x".into()), Expr::Var(Name("x".into()))),
+        args: vec![],
+        expected_scheme: Some(int_se.output_scheme),
+    };
+    let res: Result<HeaderHash, _> = conductors[0]
+        .call_fallible(
+            &cells[0].zome(ZOME_NAME),
+            "create_sensemaker_entry",
+            bad_csei,
+        )
+        .await;
+    assert!(res.is_err());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_update_history() -> anyhow::Result<()> {
+    use holochain::test_utils::consistency_10s;
+
+    use common::{CreateSensemakerEntryInput, SensemakerEntryRevision};
+    use rep_lang_core::abstract_syntax::{Expr, Lit};
+    use rep_lang_runtime::eval::{FlatValue, Value};
+
+    const NUM_CONDUCTORS: usize = 2;
+
+    let (conductors, apps) = setup_conductors_cells(NUM_CONDUCTORS).await;
+    let cells = apps.cells_flattened();
+
+    let csei = CreateSensemakerEntryInput {
+        expr: Expr::Lit(Lit::LInt(0)),
+        args: vec![],
+        expected_scheme: None,
+    };
+    let original_hh: HeaderHash = conductors[0]
+        .call(&cells[0].zome(ZOME_NAME), "create_sensemaker_entry", csei)
+        .await;
+
+    let update_csei = CreateSensemakerEntryInput {
+        expr: Expr::Lit(Lit::LInt(1)),
+        args: vec![],
+        expected_scheme: None,
+    };
+    let updated_hh: HeaderHash = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "update_sensemaker_entry",
+            (original_hh.clone(), update_csei),
+        )
+        .await;
+
+    consistency_10s(&cells).await;
+
+    // history is the same, whichever revision we start from
+    for hh in [original_hh.clone(), updated_hh.clone()] {
+        let history: Vec<SensemakerEntryRevision> = conductors[1]
+            .call(
+                &cells[1].zome(ZOME_NAME),
+                "get_sensemaker_entry_history",
+                hh,
+            )
+            .await;
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].hh, original_hh);
+        assert_eq!(history[0].replaces, None);
+        assert_eq!(history[1].hh, updated_hh);
+        assert_eq!(history[1].replaces, Some(original_hh.clone()));
+        assert_eq!(history[1].author, cells[0].agent_pubkey().clone());
+        assert_eq!(history[1].se.output_flat_value, FlatValue(Value::VInt(1)));
+    }
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_delete() -> anyhow::Result<()> {
+    use holochain::test_utils::consistency_10s;
+
+    use common::{CreateSensemakerEntryInput, Dependent, GetDependentsInput, SensemakerOperand};
+    use rep_lang_core::{
+        abstract_syntax::{Expr, Lit, PrimOp},
+        app,
+    };
+
+    const NUM_CONDUCTORS: usize = 2;
+
+    let (conductors, apps) = setup_conductors_cells(NUM_CONDUCTORS).await;
+    let cells = apps.cells_flattened();
+
+    let init_csei = CreateSensemakerEntryInput {
+        expr: Expr::Lit(Lit::LInt(0)),
+        args: vec![],
+        expected_scheme: None,
+    };
+    let init_hh: HeaderHash = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "create_sensemaker_entry",
+            init_csei,
+        )
+        .await;
+    let incr_csei = CreateSensemakerEntryInput {
+        expr: app!(Expr::Prim(PrimOp::Add), Expr::Lit(Lit::LInt(1))),
+        args: vec![SensemakerOperand::SensemakerOperand(init_hh.clone())],
+        expected_scheme: None,
+    };
+    let incr_hh: HeaderHash = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "create_sensemaker_entry",
+            incr_csei,
+        )
+        .await;
+
+    consistency_10s(&cells).await;
+
+    // only the author may delete
+    let res: Result<HeaderHash, _> = conductors[1]
+        .call_fallible(
+            &cells[1].zome(ZOME_NAME),
+            "delete_sensemaker_entry",
+            init_hh.clone(),
+        )
+        .await;
+    assert!(res.is_err());
+
+    let _delete_hh: HeaderHash = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "delete_sensemaker_entry",
+            init_hh.clone(),
+        )
+        .await;
+
+    consistency_10s(&cells).await;
+
+    let dependents: Vec<Dependent> = conductors[1]
+        .call(
+            &cells[1].zome(ZOME_NAME),
+            "get_dependents",
+            GetDependentsInput {
+                hh: init_hh.clone(),
+                max_depth: 1,
+                offset: 0,
+                limit: 10,
+            },
+        )
+        .await;
+    assert_eq!(dependents.len(), 1);
+    assert_eq!(dependents[0].hh, incr_hh);
+    assert_eq!(dependents[0].retracted_inputs, vec![init_hh]);
+
+    Ok(())
+}
+
+/// needs the DNA to be built with `--features test_utils` (`npm run hc:build:test_utils`).
+#[cfg(feature = "test_utils")]
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_scheme_entry_link_validation() -> anyhow::Result<()> {
+    use common::{CreateSensemakerEntryInput, SensemakerEntry};
+    use rep_lang_core::{
+        abstract_syntax::{Expr, Lit, Name},
+        lam,
+    };
+    use rep_lang_runtime::types::{Scheme, Type};
+    use social_sensemaker_core::OWNER_TAG;
+
+    let (conductors, apps) = setup_conductors_cells(1).await;
+    let cells = apps.cells_flattened();
+
+    let x = Name("x".into());
+    let mut se_ehs: Vec<EntryHash> = Vec::new();
+    for expr in [Expr::Lit(Lit::LInt(0)), lam!(x.clone(), Expr::Var(x))] {
+        let hh: HeaderHash = conductors[0]
+            .call(
+                &cells[0].zome(ZOME_NAME),
+                "create_sensemaker_entry",
+                CreateSensemakerEntryInput {
+                    expr,
+                    args: vec![],
+                    expected_scheme: None,
+                },
+            )
+            .await;
+        let (eh, _se): (EntryHash, SensemakerEntry) = conductors[0]
+            .call(
+                &cells[0].zome(ZOME_NAME),
+                "get_sensemaker_entry_by_headerhash",
+                hh,
+            )
+            .await;
+        se_ehs.push(eh);
+    }
+
+    // the identity is not an `Int`, so it may not be indexed as one, whatever the tag.
+    let int_sc = Scheme(vec![], Type::TCon("Int".into()));
+    for tag in [OWNER_TAG.to_string(), format!("{}_x", OWNER_TAG)] {
+        let res: Result<HeaderHash, _> = conductors[0]
+            .call_fallible(
+                &cells[0].zome(ZOME_NAME),
+                "create_scheme_entry_link_raw",
+                (int_sc.clone(), se_ehs[1].clone(), LinkTag::new(tag)),
+            )
+            .await;
+        assert!(res.is_err());
+    }
+
+    let ints: Vec<(HeaderHash, SensemakerEntry)> = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "get_sensemaker_entries_which_unify",
+            Some(int_sc),
+        )
+        .await;
+    assert_eq!(ints.len(), 1);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_which_unify_paginated() -> anyhow::Result<()> {
+    use common::{
+        CreateSensemakerEntryInput, SensemakerEntriesPage, SensemakerEntry, UnifyPageInput,
+    };
+    use rep_lang_core::abstract_syntax::{Expr, Lit};
+    use rep_lang_runtime::types::{Scheme, Type};
+
+    let (conductors, apps) = setup_conductors_cells(1).await;
+    let cells = apps.cells_flattened();
+
+    let mut int_hh_s: Vec<HeaderHash> = Vec::new();
+    for n in 0..5 {
+        let hh: HeaderHash = conductors[0]
+            .call(
+                &cells[0].zome(ZOME_NAME),
+                "create_sensemaker_entry",
+                CreateSensemakerEntryInput {
+                    expr: Expr::Lit(Lit::LInt(n)),
+                    args: vec![],
+                    expected_scheme: None,
+                },
+            )
+            .await;
+        int_hh_s.push(hh);
+    }
+    let _deleted_hh: HeaderHash = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "delete_sensemaker_entry",
+            int_hh_s.remove(2),
+        )
+        .await;
+
+    // follow the cursor until the last page, which has none.
+    let mut pages: Vec<SensemakerEntriesPage> = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page: SensemakerEntriesPage = conductors[0]
+            .call(
+                &cells[0].zome(ZOME_NAME),
+                "get_sensemaker_entries_which_unify_paginated",
+                UnifyPageInput {
+                    opt_target_sc: Some(Scheme(vec![], Type::TCon("Int".into()))),
+                    cursor,
+                    page_size: 2,
+                    include_total: true,
+                },
+            )
+            .await;
+        assert_eq!(page.total, Some(4));
+        cursor = page.next_cursor.clone();
+        pages.push(page);
+        if cursor.is_none() {
+            break;
+        }
+    }
+    assert_eq!(pages.len(), 2);
+    let paged_hh_s: Vec<HeaderHash> = pages
+        .into_iter()
+        .flat_map(|page| page.entries)
+        .map(|(hh, _se): (HeaderHash, SensemakerEntry)| hh)
+        .collect();
+    // pages follow creation order.
+    assert_eq!(paged_hh_s, int_hh_s);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_get_dependents_paging() -> anyhow::Result<()> {
+    use common::{CreateSensemakerEntryInput, Dependent, GetDependentsInput, SensemakerOperand};
+    use rep_lang_core::{
+        abstract_syntax::{Expr, Lit, PrimOp},
+        app,
+    };
+
+    let (conductors, apps) = setup_conductors_cells(1).await;
+    let cells = apps.cells_flattened();
+    let zome = cells[0].zome(ZOME_NAME);
+
+    let create = |expr: Expr, args: Vec<HeaderHash>| {
+        conductors[0].call::<_, HeaderHash, _>(
+            &zome,
+            "create_sensemaker_entry",
+            CreateSensemakerEntryInput {
+                expr,
+                args: args
+                    .into_iter()
+                    .map(SensemakerOperand::SensemakerOperand)
+                    .collect(),
+                expected_scheme: None,
+            },
+        )
+    };
+    let incr = || app!(Expr::Prim(PrimOp::Add), Expr::Lit(Lit::LInt(1)));
+
+    // root <- a <- c, and root <- b.
+    let root_hh = create(Expr::Lit(Lit::LInt(0)), vec![]).await;
+    let a_hh = create(incr(), vec![root_hh.clone()]).await;
+    let b_hh = create(incr(), vec![root_hh.clone()]).await;
+    let c_hh = create(incr(), vec![a_hh.clone()]).await;
+
+    let get_dependents = |max_depth, offset, limit| {
+        conductors[0].call::<_, Vec<Dependent>, _>(
+            &zome,
+            "get_dependents",
+            GetDependentsInput {
+                hh: root_hh.clone(),
+                max_depth,
+                offset,
+                limit,
+            },
+        )
+    };
+    let hh_depths = |dependents: Vec<Dependent>| {
+        dependents
+            .into_iter()
+            .map(|dependent| (dependent.hh, dependent.depth))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        hh_depths(get_dependents(1, 0, 10).await),
+        vec![(a_hh.clone(), 1), (b_hh.clone(), 1)]
+    );
+    assert_eq!(
+        hh_depths(get_dependents(2, 0, 10).await),
+        vec![(a_hh.clone(), 1), (b_hh.clone(), 1), (c_hh.clone(), 2)]
+    );
+    assert_eq!(hh_depths(get_dependents(2, 1, 1).await), vec![(b_hh, 1)]);
+    assert_eq!(hh_depths(get_dependents(2, 2, 10).await), vec![(c_hh, 2)]);
+    assert!(get_dependents(2, 3, 10).await.is_empty());
+
+    Ok(())
+}
+
+/// needs the DNA to be built with `--features test_utils` (`npm run hc:build:test_utils`).
+#[cfg(feature = "test_utils")]
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_tampered_start_gas() -> anyhow::Result<()> {
+    use common::{CreateSensemakerEntryInput, SensemakerEntry, SensemakerEntryPreview};
+    use rep_lang_core::{
+        abstract_syntax::{Expr, Lit, PrimOp},
+        app,
+    };
+
+    let (conductors, apps) = setup_conductors_cells(1).await;
+    let cells = apps.cells_flattened();
+
+    let expr = app!(
+        app!(Expr::Prim(PrimOp::Add), Expr::Lit(Lit::LInt(1))),
+        Expr::Lit(Lit::LInt(2))
+    );
+    let preview: SensemakerEntryPreview = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "preview_sensemaker_entry",
+            CreateSensemakerEntryInput {
+                expr: expr.clone(),
+                args: vec![],
+                expected_scheme: None,
+            },
+        )
+        .await;
+    let se = SensemakerEntry {
+        operator: expr,
+        operands: vec![],
+        output_scheme: preview.output_scheme,
+        output_flat_value: preview.output_flat_value,
+        start_gas: preview.start_gas,
+    };
+
+    // the honest entry is accepted
+    let _hh: HeaderHash = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "create_sensemaker_entry_raw",
+            se.clone(),
+        )
+        .await;
+
+    // under-reporting gas is not
+    let tampered_se = SensemakerEntry {
+        start_gas: se.start_gas - 1,
+        ..se
+    };
+    let res: Result<HeaderHash, _> = conductors[0]
+        .call_fallible(
+            &cells[0].zome(ZOME_NAME),
+            "create_sensemaker_entry_raw",
+            tampered_se,
+        )
+        .await;
+    assert!(res.is_err());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_create_sensemaker_entry_parse() -> anyhow::Result<()> {
+    use common::{
+        CreateSensemakerEntryInput, CreateSensemakerEntryInputParse, SensemakerEntry,
+        SensemakerOperand, OTHER_OPERAND_PREFIX, SENSEMAKER_OPERAND_PREFIX,
+    };
+    use rep_lang_core::{
+        abstract_syntax::{Expr, Lit, Name},
+        lam,
+    };
+    use rep_lang_runtime::eval::{FlatValue, Value};
+
+    let (conductors, apps) = setup_conductors_cells(1).await;
+    let cells = apps.cells_flattened();
+
+    let mut int_hh_s: Vec<HeaderHash> = Vec::new();
+    for n in 0..2 {
+        let hh: HeaderHash = conductors[0]
+            .call(
+                &cells[0].zome(ZOME_NAME),
+                "create_sensemaker_entry",
+                CreateSensemakerEntryInput {
+                    expr: Expr::Lit(Lit::LInt(n)),
+                    args: vec![],
+                    expected_scheme: None,
+                },
+            )
+            .await;
+        int_hh_s.push(hh);
+    }
+    let (se_hh, other_hh) = (int_hh_s[0].clone(), int_hh_s[1].clone());
+    let se_arg = format!("{}{}", SENSEMAKER_OPERAND_PREFIX, se_hh);
+    let other_arg = format!("{}{}", OTHER_OPERAND_PREFIX, other_hh);
+
+    // keeps its first argument, & ignores the other entry's bytes
+    let (x, y) = (Name("x".into()), Name("y".into()));
+    let konst = render_expr(&lam!(x.clone(), lam!(y, Expr::Var(x))));
+    let (_hh, se): (HeaderHash, SensemakerEntry) = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "create_sensemaker_entry_parse",
+            CreateSensemakerEntryInputParse {
+                expr: konst.clone(),
+                args: vec![se_arg.clone(), other_arg],
+            },
+        )
+        .await;
+    assert_eq!(
+        se.operands,
+        vec![
+            SensemakerOperand::SensemakerOperand(se_hh.clone()),
+            SensemakerOperand::OtherOperand(other_hh),
+        ]
+    );
+    assert_eq!(se.output_flat_value, FlatValue(Value::VInt(0)));
+
+    let unprefixed = se_hh.to_string();
+    let malformed = format!("{}not-a-hash", SENSEMAKER_OPERAND_PREFIX);
+    for (args, arg_idx) in vec![
+        (vec![se_arg.clone(), unprefixed], 1),
+        (vec![malformed, se_arg], 0),
+    ] {
+        let res: Result<(HeaderHash, SensemakerEntry), _> = conductors[0]
+            .call_fallible(
+                &cells[0].zome(ZOME_NAME),
+                "create_sensemaker_entry_parse",
+                CreateSensemakerEntryInputParse {
+                    expr: konst.clone(),
+                    args,
+                },
+            )
+            .await;
+        // the error names the offending argument.
+        let arg_idx_field = format!("\"arg_idx\":{}", arg_idx);
+        let err_str = format!("{:?}", res);
+        assert!(is_sensemaker_error(&res, "OperandParse"));
+        assert!(
+            err_str.contains(&arg_idx_field)
+                || err_str.contains(&arg_idx_field.replace('"', "\\\""))
+        );
+    }
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_other_operand() -> anyhow::Result<()> {
+    use common::{
+        bytes_to_scheme_flat_value, CreateSensemakerEntryInput, SensemakerEntry, SensemakerOperand,
+    };
+    use rep_lang_core::{
+        abstract_syntax::{Expr, Lit},
+        lam,
+    };
+    use rep_lang_runtime::eval::EvalState;
+
+    let (conductors, apps) = setup_conductors_cells(1).await;
+    let cells = apps.cells_flattened();
+
+    let other_hh: HeaderHash = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "create_sensemaker_entry",
+            CreateSensemakerEntryInput {
+                expr: Expr::Lit(Lit::LInt(0)),
+                args: vec![],
+                expected_scheme: None,
+            },
+        )
+        .await;
+    let (_other_eh, other_se): (EntryHash, SensemakerEntry) = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "get_sensemaker_entry_by_headerhash",
+            other_hh.clone(),
+        )
+        .await;
+
+    // the identity, applied to the other entry's bytes
+    let nm = EvalState::new().fresh_name();
+    let hh: HeaderHash = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "create_sensemaker_entry",
+            CreateSensemakerEntryInput {
+                expr: lam!(nm.clone(), Expr::Var(nm)),
+                args: vec![SensemakerOperand::OtherOperand(other_hh)],
+                expected_scheme: None,
+            },
+        )
+        .await;
+    let (_eh, se): (EntryHash, SensemakerEntry) = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "get_sensemaker_entry_by_headerhash",
+            hh,
+        )
+        .await;
+
+    let other_sb = SerializedBytes::try_from(other_se)?;
+    let (expected_sc, expected_flat_value) = bytes_to_scheme_flat_value(other_sb.bytes());
+    assert_eq!(se.output_scheme, expected_sc);
+    assert_eq!(se.output_flat_value, expected_flat_value);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_explain_gas() -> anyhow::Result<()> {
+    use common::{
+        explain::SensemakerEntryExplanation, CreateSensemakerEntryInput, SensemakerEntry,
+        SensemakerOperand,
+    };
+    use rep_lang_core::{
+        abstract_syntax::{Expr, Lit, PrimOp},
+        app,
+    };
+
+    let (conductors, apps) = setup_conductors_cells(1).await;
+    let cells = apps.cells_flattened();
+
+    let init_hh: HeaderHash = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "create_sensemaker_entry",
+            CreateSensemakerEntryInput {
+                expr: Expr::Lit(Lit::LInt(2)),
+                args: vec![],
+                expected_scheme: None,
+            },
+        )
+        .await;
+    let hh: HeaderHash = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "create_sensemaker_entry",
+            CreateSensemakerEntryInput {
+                expr: app!(Expr::Prim(PrimOp::Add), Expr::Lit(Lit::LInt(1))),
+                args: vec![SensemakerOperand::SensemakerOperand(init_hh)],
+                expected_scheme: None,
+            },
+        )
+        .await;
+    let (_eh, se): (EntryHash, SensemakerEntry) = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "get_sensemaker_entry_by_headerhash",
+            hh.clone(),
+        )
+        .await;
+
+    let explanation: SensemakerEntryExplanation = conductors[0]
+        .call(&cells[0].zome(ZOME_NAME), "explain_sensemaker_entry", hh)
+        .await;
+    assert_eq!(explanation.bindings.len(), 1);
+    assert_eq!(explanation.output_flat_value, se.output_flat_value);
+    assert_eq!(explanation.total_gas, se.start_gas);
+    let phases = &explanation.phases;
+    let phases_gas = phases.setup_gas as u64 + phases.eval_gas as u64 + phases.force_gas as u64;
+    assert_eq!(phases_gas, se.start_gas as u64);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_concurrent_step_sm() -> anyhow::Result<()> {
+    use holochain::test_utils::consistency_10s;
+
+    use common::StateMachineSchemes;
+    use rep_lang_runtime::eval::{FlatValue, Value};
+
+    let (conductors, apps) = setup_conductors_cells(2).await;
+    let cells = apps.cells_flattened();
+
+    let path_prefix = "widget.posts".to_string();
+    let (sm_comp, sm_init) = counter_sm();
+    let _: StateMachineSchemes = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "register_state_machine",
+            (path_prefix.clone(), sm_comp, sm_init),
+        )
+        .await;
+    let _: () = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "initialize_sm_data_path",
+            (path_prefix.clone(), "a".to_string()),
+        )
+        .await;
+    consistency_10s(&cells).await;
+
+    // both agents step without waiting for consistency, so each is likely to step from
+    // the initial state.
+    for (idx, act) in [(0, 1), (1, 10)] {
+        let _: () = conductors[idx]
+            .call(
+                &cells[idx].zome(ZOME_NAME),
+                "step_sm_path",
+                (path_prefix.clone(), "a".to_string(), int_source(act)),
+            )
+            .await;
+    }
+    consistency_10s(&cells).await;
+
+    for idx in 0..2 {
+        let sm_data: Option<FlatValue<()>> = conductors[idx]
+            .call(
+                &cells[idx].zome(ZOME_NAME),
+                "get_sm_data_path",
+                (path_prefix.clone(), "a".to_string()),
+            )
+            .await;
+        assert_eq!(sm_data, Some(FlatValue(Value::VInt(11))));
+    }
+
+    // a later step builds on both
+    let _: () = conductors[1]
+        .call(
+            &cells[1].zome(ZOME_NAME),
+            "step_sm_path",
+            (path_prefix.clone(), "a".to_string(), int_source(100)),
+        )
+        .await;
+    let sm_data: Option<FlatValue<()>> = conductors[1]
+        .call(
+            &cells[1].zome(ZOME_NAME),
+            "get_sm_data_path",
+            (path_prefix, "a".to_string()),
+        )
+        .await;
+    assert_eq!(sm_data, Some(FlatValue(Value::VInt(111))));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_step_sm_batch_then_step() -> anyhow::Result<()> {
+    use common::{SmHistoryEntry, StateMachineSchemes};
+    use rep_lang_runtime::eval::{FlatValue, Value};
+
+    let (conductors, apps) = setup_conductors_cells(1).await;
+    let cells = apps.cells_flattened();
+
+    let path_prefix = "widget.posts".to_string();
+    let (sm_comp, sm_init) = counter_sm();
+    let _: StateMachineSchemes = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "register_state_machine",
+            (path_prefix.clone(), sm_comp, sm_init),
+        )
+        .await;
+    let _: () = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "initialize_sm_data_path",
+            (path_prefix.clone(), "a".to_string()),
+        )
+        .await;
+
+    let acts: Vec<String> = [1, 2, 3].iter().map(|n| int_source(*n)).collect();
+    let _: () = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "step_sm_batch_path",
+            (path_prefix.clone(), "a".to_string(), acts),
+        )
+        .await;
+    let _: () = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "step_sm_path",
+            (path_prefix.clone(), "a".to_string(), int_source(4)),
+        )
+        .await;
+
+    let sm_data: Option<FlatValue<()>> = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "get_sm_data_path",
+            (path_prefix.clone(), "a".to_string()),
+        )
+        .await;
+    assert_eq!(sm_data, Some(FlatValue(Value::VInt(10))));
+
+    // every action of the batch is recorded, though only its last state is linked
+    let history: Vec<SmHistoryEntry> = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "get_sm_history_path",
+            (path_prefix, "a".to_string()),
+        )
+        .await;
+    assert_eq!(history[1].link_hh, history[3].link_hh);
+    assert_ne!(history[3].link_hh, history[4].link_hh);
+    let states: Vec<FlatValue<()>> = history.into_iter().map(|entry| entry.state).collect();
+    let expected_states: Vec<FlatValue<()>> = [0, 1, 3, 6, 10]
+        .iter()
+        .map(|n| FlatValue(Value::VInt(*n)))
+        .collect();
+    assert_eq!(states, expected_states);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_step_sm_batch_concurrent() -> anyhow::Result<()> {
+    use holochain::test_utils::consistency_10s;
+
+    use common::StateMachineSchemes;
+    use rep_lang_runtime::eval::{FlatValue, Value};
+
+    let (conductors, apps) = setup_conductors_cells(2).await;
+    let cells = apps.cells_flattened();
+
+    let path_prefix = "widget.posts".to_string();
+    let (sm_comp, sm_init) = counter_sm();
+    let _: StateMachineSchemes = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "register_state_machine",
+            (path_prefix.clone(), sm_comp, sm_init),
+        )
+        .await;
+    let _: () = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "initialize_sm_data_path",
+            (path_prefix.clone(), "a".to_string()),
+        )
+        .await;
+    consistency_10s(&cells).await;
+
+    // whichever is linked second forks, & all of its actions are re-applied
+    let acts: Vec<String> = [1, 2, 3].iter().map(|n| int_source(*n)).collect();
+    let _: () = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "step_sm_batch_path",
+            (path_prefix.clone(), "a".to_string(), acts),
+        )
+        .await;
+    let _: () = conductors[1]
+        .call(
+            &cells[1].zome(ZOME_NAME),
+            "step_sm_path",
+            (path_prefix.clone(), "a".to_string(), int_source(4)),
+        )
+        .await;
+    consistency_10s(&cells).await;
+
+    for idx in 0..2 {
+        let sm_data: Option<FlatValue<()>> = conductors[idx]
+            .call(
+                &cells[idx].zome(ZOME_NAME),
+                "get_sm_data_path",
+                (path_prefix.clone(), "a".to_string()),
+            )
+            .await;
+        assert_eq!(sm_data, Some(FlatValue(Value::VInt(10))));
+    }
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_sm_snapshot_keeps_concurrent_steps() -> anyhow::Result<()> {
+    use holochain::test_utils::consistency_10s;
+
+    use common::StateMachineSchemes;
+    use rep_lang_runtime::eval::{FlatValue, Value};
+
+    let (conductors, apps) = setup_conductors_cells(2).await;
+    let cells = apps.cells_flattened();
+
+    let path_prefix = "widget.posts".to_string();
+    let (sm_comp, sm_init) = counter_sm();
+    let _: StateMachineSchemes = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "register_state_machine",
+            (path_prefix.clone(), sm_comp, sm_init),
+        )
+        .await;
+    let _: () = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "initialize_sm_data_path",
+            (path_prefix.clone(), "a".to_string()),
+        )
+        .await;
+    consistency_10s(&cells).await;
+
+    // the snapshot is likely taken before its author sees the other agent's step
+    for (idx, act) in [(0, 1), (1, 10)] {
+        let _: () = conductors[idx]
+            .call(
+                &cells[idx].zome(ZOME_NAME),
+                "step_sm_path",
+                (path_prefix.clone(), "a".to_string(), int_source(act)),
+            )
+            .await;
+    }
+    let _snapshot_hh: HeaderHash = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "snapshot_sm_data_path",
+            (path_prefix.clone(), "a".to_string()),
+        )
+        .await;
+    consistency_10s(&cells).await;
+
+    for idx in 0..2 {
+        let sm_data: Option<FlatValue<()>> = conductors[idx]
+            .call(
+                &cells[idx].zome(ZOME_NAME),
+                "get_sm_data_path",
+                (path_prefix.clone(), "a".to_string()),
+            )
+            .await;
+        assert_eq!(sm_data, Some(FlatValue(Value::VInt(11))));
+    }
+
+    let _: () = conductors[1]
+        .call(
+            &cells[1].zome(ZOME_NAME),
+            "step_sm_path",
+            (path_prefix.clone(), "a".to_string(), int_source(100)),
+        )
+        .await;
+    let sm_data: Option<FlatValue<()>> = conductors[1]
+        .call(
+            &cells[1].zome(ZOME_NAME),
+            "get_sm_data_path",
+            (path_prefix, "a".to_string()),
+        )
+        .await;
+    assert_eq!(sm_data, Some(FlatValue(Value::VInt(111))));
+
+    Ok(())
+}
+
+/// needs the DNA to be built with `--features test_utils` (`npm run hc:build:test_utils`).
+#[cfg(feature = "test_utils")]
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_sm_snapshot_link_validation() -> anyhow::Result<()> {
+    use common::{
+        CreateSensemakerEntryInput, SensemakerEntry, SmHistoryEntry, SmSnapshotTag,
+        StateMachineSchemes,
+    };
+    use rep_lang_core::abstract_syntax::{Expr, Lit};
+    use rep_lang_runtime::eval::{FlatValue, Value};
+
+    let (conductors, apps) = setup_conductors_cells(1).await;
+    let cells = apps.cells_flattened();
+
+    let path_prefix = "widget.posts".to_string();
+    let (sm_comp, sm_init) = counter_sm();
+    let _: StateMachineSchemes = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "register_state_machine",
+            (path_prefix.clone(), sm_comp, sm_init),
+        )
+        .await;
+    for suffix in ["a", "b"] {
+        let _: () = conductors[0]
+            .call(
+                &cells[0].zome(ZOME_NAME),
+                "initialize_sm_data_path",
+                (path_prefix.clone(), suffix.to_string()),
+            )
+            .await;
+    }
+    let _: () = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "step_sm_path",
+            (path_prefix.clone(), "a".to_string(), int_source(1)),
+        )
+        .await;
+    let history: Vec<SmHistoryEntry> = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "get_sm_history_path",
+            (path_prefix.clone(), "a".to_string()),
+        )
+        .await;
+    let stepped = history.last().expect("a was stepped");
+    let tag = SmSnapshotTag {
+        state_hh: stepped.state_hh.clone(),
+        cutoff_link_hh: stepped.link_hh.clone(),
+        num_links: history.len(),
+    }
+    .to_link_tag()
+    .unwrap();
+
+    let mut literal_ehs: Vec<EntryHash> = Vec::new();
+    for n in [1, 2] {
+        let hh: HeaderHash = conductors[0]
+            .call(
+                &cells[0].zome(ZOME_NAME),
+                "create_sensemaker_entry",
+                CreateSensemakerEntryInput {
+                    expr: Expr::Lit(Lit::LInt(n)),
+                    args: vec![],
+                    expected_scheme: None,
+                },
+            )
+            .await;
+        let (eh, _se): (EntryHash, SensemakerEntry) = conductors[0]
+            .call(
+                &cells[0].zome(ZOME_NAME),
+                "get_sensemaker_entry_by_headerhash",
+                hh,
+            )
+            .await;
+        literal_ehs.push(eh);
+    }
+
+    // a snapshot of `a`'s state may not be linked under `b`, nor may one with another
+    // value be linked under `a`.
+    for (suffix, literal_eh) in [("b", literal_ehs[0].clone()), ("a", literal_ehs[1].clone())] {
+        let res: Result<HeaderHash, _> = conductors[0]
+            .call_fallible(
+                &cells[0].zome(ZOME_NAME),
+                "create_link_raw",
+                (
+                    format!("{}.{}", path_prefix, suffix),
+                    literal_eh,
+                    tag.clone(),
+                ),
+            )
+            .await;
+        assert!(res.is_err());
+    }
+
+    let _: HeaderHash = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "create_link_raw",
+            (
+                format!("{}.{}", path_prefix, "a"),
+                literal_ehs[0].clone(),
+                tag,
+            ),
+        )
+        .await;
+    let _: () = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "step_sm_path",
+            (path_prefix.clone(), "a".to_string(), int_source(10)),
+        )
+        .await;
+    let sm_data: Option<FlatValue<()>> = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "get_sm_data_path",
+            (path_prefix, "a".to_string()),
+        )
+        .await;
+    assert_eq!(sm_data, Some(FlatValue(Value::VInt(11))));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_list_sm_data() -> anyhow::Result<()> {
+    use common::{SmDataListing, SortOrder, StateMachineSchemes};
+    use rep_lang_runtime::eval::{FlatValue, Value};
+
+    let (conductors, apps) = setup_conductors_cells(1).await;
+    let cells = apps.cells_flattened();
+
+    let path_prefix = "widget.posts".to_string();
+    let (sm_comp, sm_init) = counter_sm();
+    let _: StateMachineSchemes = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "register_state_machine",
+            (path_prefix.clone(), sm_comp, sm_init),
+        )
+        .await;
+
+    // every target starts at `0`, then `b` & `c` are stepped.
+    for suffix in ["a", "b", "c"] {
+        let _: () = conductors[0]
+            .call(
+                &cells[0].zome(ZOME_NAME),
+                "initialize_sm_data_path",
+                (path_prefix.clone(), suffix.to_string()),
+            )
+            .await;
+    }
+    for (suffix, act) in [("b", 2), ("b", 3), ("c", 3)] {
+        let _: () = conductors[0]
+            .call(
+                &cells[0].zome(ZOME_NAME),
+                "step_sm_path",
+                (path_prefix.clone(), suffix.to_string(), int_source(act)),
+            )
+            .await;
+    }
+
+    let listings: Vec<SmDataListing> = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "list_sm_data",
+            (
+                path_prefix.clone(),
+                Some(SortOrder::Descending),
+                Some(2_usize),
+            ),
+        )
+        .await;
+    let summary: Vec<(String, FlatValue<()>)> = listings
+        .into_iter()
+        .map(|listing| (listing.path_suffix, listing.state))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("b".to_string(), FlatValue(Value::VInt(5))),
+            ("c".to_string(), FlatValue(Value::VInt(3))),
+        ]
+    );
+
+    let listings: Vec<SmDataListing> = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "list_sm_data",
+            (path_prefix, None::<SortOrder>, None::<usize>),
+        )
+        .await;
+    assert_eq!(listings.len(), 3);
+
+    Ok(())
+}
+
+/// needs the DNA to be built with `--features test_utils` (`npm run hc:build:test_utils`).
+#[cfg(feature = "test_utils")]
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_gas_budget_exceeded() -> anyhow::Result<()> {
+    use common::{CreateSensemakerEntryInput, SensemakerEntry, SensemakerEntryPreview};
+    use rep_lang_core::abstract_syntax::{Expr, Lit};
+
+    let (conductors, apps) = setup_conductors_cells(1).await;
+    let cells = apps.cells_flattened();
+
+    let expr = Expr::Lit(Lit::LInt(0));
+    let preview: SensemakerEntryPreview = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "preview_sensemaker_entry",
+            CreateSensemakerEntryInput {
+                expr: expr.clone(),
+                args: vec![],
+                expected_scheme: None,
+            },
+        )
+        .await;
+
+    // an entry which admits to exceeding `max_gas` (see `dna.yaml`) is rejected by
+    // validation, before it is evaluated.
+    let se = SensemakerEntry {
+        operator: expr,
+        operands: vec![],
+        output_scheme: preview.output_scheme,
+        output_flat_value: preview.output_flat_value,
+        start_gas: 1_000_001,
+    };
+    let res: Result<HeaderHash, _> = conductors[0]
+        .call_fallible(&cells[0].zome(ZOME_NAME), "create_sensemaker_entry_raw", se)
+        .await;
+    assert!(res.is_err());
+    assert!(format!("{:?}", res).contains("gas budget exceeded"));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_gas_budget_exceeded_during_evaluation() -> anyhow::Result<()> {
+    use common::CreateSensemakerEntryInput;
+    use rep_lang_core::{
+        abstract_syntax::{Expr, Lit, Name, PrimOp},
+        app, lam,
+    };
+
+    let (conductors, apps) = setup_conductors_cells(1).await;
+    let cells = apps.cells_flattened();
+
+    let var = |nm: &str| Expr::Var(Name(nm.into()));
+    let let_in =
+        |nm: &str, bound, body| Expr::Let(Name(nm.into()), Box::new(bound), Box::new(body));
+    let add = |e1, e2| app!(app!(Expr::Prim(PrimOp::Add), e1), e2);
+    let zero = || Expr::Lit(Lit::LInt(0));
+
+    // Church numerals: `n256 f` applies `f` 256 times, `n4096 f` 4096 times.
+    let twice = lam!(
+        Name("f".into()),
+        lam!(Name("x".into()), app!(var("f"), app!(var("f"), var("x"))))
+    );
+    let n16 = app!(app!(var("twice"), var("twice")), var("twice"));
+    let n256 = app!(var("twice"), var("n16"));
+    let n4096 = lam!(
+        Name("g".into()),
+        app!(var("n16"), app!(var("n256"), var("g")))
+    );
+    // sums 256, 4096 times: over 1M additions, which exceeds `max_gas` (see `dna.yaml`).
+    let inc = lam!(Name("m".into()), add(var("m"), Expr::Lit(Lit::LInt(1))));
+    let add_256 = lam!(
+        Name("acc".into()),
+        add(var("acc"), app!(app!(var("n256"), inc), zero()))
+    );
+    let expr = let_in(
+        "twice",
+        twice,
+        let_in(
+            "n16",
+            n16,
+            let_in(
+                "n256",
+                n256,
+                let_in("n4096", n4096, app!(app!(var("n4096"), add_256), zero())),
+            ),
+        ),
+    );
+
+    let res: Result<HeaderHash, _> = conductors[0]
+        .call_fallible(
+            &cells[0].zome(ZOME_NAME),
+            "create_sensemaker_entry",
+            CreateSensemakerEntryInput {
+                expr,
+                args: vec![],
+                expected_scheme: None,
+            },
+        )
+        .await;
+    assert!(is_sensemaker_error(&res, "GasBudgetExceeded"));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_limit_exceeded() -> anyhow::Result<()> {
+    use common::{CreateSensemakerEntryInput, SensemakerOperand};
+    use rep_lang_core::{
+        abstract_syntax::{Expr, Lit, PrimOp},
+        app,
+    };
+
+    let (conductors, apps) = setup_conductors_cells(1).await;
+    let cells = apps.cells_flattened();
+
+    let zero_hh: HeaderHash = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "create_sensemaker_entry",
+            CreateSensemakerEntryInput {
+                expr: Expr::Lit(Lit::LInt(0)),
+                args: vec![],
+                expected_scheme: None,
+            },
+        )
+        .await;
+
+    // more operands than `max_operands` (see `dna.yaml`). the count is checked before
+    // the operator is type-checked, or any operand dereferenced.
+    let res: Result<HeaderHash, _> = conductors[0]
+        .call_fallible(
+            &cells[0].zome(ZOME_NAME),
+            "create_sensemaker_entry",
+            CreateSensemakerEntryInput {
+                expr: Expr::Lit(Lit::LInt(0)),
+                args: vec![SensemakerOperand::SensemakerOperand(zero_hh); 257],
+                expected_scheme: None,
+            },
+        )
+        .await;
+    assert!(is_sensemaker_error(&res, "LimitExceeded"));
+    assert!(format!("{:?}", res).contains("max_operands"));
+
+    // `1 + (1 + (.. + 0))`, nested deeper than `max_expr_depth`
+    let deep_expr = (0..600).fold(Expr::Lit(Lit::LInt(0)), |acc, _| {
+        app!(app!(Expr::Prim(PrimOp::Add), Expr::Lit(Lit::LInt(1))), acc)
+    });
+    let res: Result<HeaderHash, _> = conductors[0]
+        .call_fallible(
+            &cells[0].zome(ZOME_NAME),
+            "create_sensemaker_entry",
+            CreateSensemakerEntryInput {
+                expr: deep_expr,
+                args: vec![],
+                expected_scheme: None,
+            },
+        )
+        .await;
+    assert!(is_sensemaker_error(&res, "LimitExceeded"));
+    assert!(format!("{:?}", res).contains("max_expr_depth"));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_register_state_machine() -> anyhow::Result<()> {
+    use common::StateMachineSchemes;
+    use rep_lang_core::{
+        abstract_syntax::{Expr, Name, PrimOp},
+        lam,
+    };
+    use rep_lang_runtime::types::{Scheme, Type};
+
+    let (conductors, apps) = setup_conductors_cells(1).await;
+    let cells = apps.cells_flattened();
+
+    let (sm_comp, sm_init) = counter_sm();
+    let schemes: StateMachineSchemes = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "register_state_machine",
+            ("widget.posts".to_string(), sm_comp.clone(), sm_init),
+        )
+        .await;
+    let int_sc = Scheme(vec![], Type::TCon("Int".into()));
+    assert_eq!(schemes.data, int_sc);
+    assert_eq!(schemes.action, int_sc);
+
+    // `sm_comp` steps `Int`s, so a list cannot be its initial state
+    let res: Result<StateMachineSchemes, _> = conductors[0]
+        .call_fallible(
+            &cells[0].zome(ZOME_NAME),
+            "register_state_machine",
+            (
+                "widget.comments".to_string(),
+                sm_comp,
+                render_expr(&Expr::Prim(PrimOp::Nil)),
+            ),
+        )
+        .await;
+    assert!(is_sensemaker_error(&res, "Type"));
+
+    // and nothing was registered under that prefix
+    let res: Result<(), _> = conductors[0]
+        .call_fallible(
+            &cells[0].zome(ZOME_NAME),
+            "initialize_sm_data_path",
+            ("widget.comments".to_string(), "a".to_string()),
+        )
+        .await;
+    assert!(is_sensemaker_error(&res, "StateMachine"));
+
+    // `\d -> \a -> Nil` would step an `Int` to a list, so it is no state machine, for
+    // all that it could be applied to its own output.
+    let (d, a) = (Name("d".into()), Name("a".into()));
+    let res: Result<StateMachineSchemes, _> = conductors[0]
+        .call_fallible(
+            &cells[0].zome(ZOME_NAME),
+            "register_state_machine",
+            (
+                "widget.likes".to_string(),
+                render_expr(&lam!(d, lam!(a, Expr::Prim(PrimOp::Nil)))),
+                int_source(0),
+            ),
+        )
+        .await;
+    assert!(is_sensemaker_error(&res, "Type"));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_sm_history() -> anyhow::Result<()> {
+    use common::{SmHistoryEntry, StateMachineSchemes};
+    use rep_lang_core::abstract_syntax::{Expr, Lit};
+    use rep_lang_runtime::eval::{FlatValue, Value};
+
+    let (conductors, apps) = setup_conductors_cells(1).await;
+    let cells = apps.cells_flattened();
+
+    let path_prefix = "widget.posts".to_string();
+    let (sm_comp, sm_init) = counter_sm();
+    let _: StateMachineSchemes = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "register_state_machine",
+            (path_prefix.clone(), sm_comp, sm_init),
+        )
+        .await;
+    let _: () = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "initialize_sm_data_path",
+            (path_prefix.clone(), "a".to_string()),
+        )
+        .await;
+    for act in [1, 2] {
+        let _: () = conductors[0]
+            .call(
+                &cells[0].zome(ZOME_NAME),
+                "step_sm_path",
+                (path_prefix.clone(), "a".to_string(), int_source(act)),
+            )
+            .await;
+    }
+
+    let history: Vec<SmHistoryEntry> = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "get_sm_history_path",
+            (path_prefix, "a".to_string()),
+        )
+        .await;
+    let summary: Vec<(FlatValue<()>, Option<Expr>)> = history
+        .iter()
+        .map(|entry| (entry.state.clone(), entry.action.clone()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (FlatValue(Value::VInt(0)), None),
+            (FlatValue(Value::VInt(1)), Some(Expr::Lit(Lit::LInt(1)))),
+            (FlatValue(Value::VInt(3)), Some(Expr::Lit(Lit::LInt(2)))),
+        ]
+    );
+    for entry in history.iter() {
+        assert_eq!(&entry.agent, cells[0].agent_pubkey());
+    }
+    assert!(history
+        .windows(2)
+        .all(|pair| pair[0].timestamp <= pair[1].timestamp));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_step_sm_mistyped_action() -> anyhow::Result<()> {
+    use common::{SmHistoryEntry, StateMachineSchemes};
+    use rep_lang_core::abstract_syntax::{Expr, PrimOp};
+    use rep_lang_runtime::eval::{FlatValue, Value};
+
+    let (conductors, apps) = setup_conductors_cells(1).await;
+    let cells = apps.cells_flattened();
+
+    let path_prefix = "widget.posts".to_string();
+    let (sm_comp, sm_init) = counter_sm();
+    let _: StateMachineSchemes = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "register_state_machine",
+            (path_prefix.clone(), sm_comp, sm_init),
+        )
+        .await;
+    let _: () = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "initialize_sm_data_path",
+            (path_prefix.clone(), "a".to_string()),
+        )
+        .await;
+
+    // the counter's actions are `Int`s, not lists
+    let res: Result<(), _> = conductors[0]
+        .call_fallible(
+            &cells[0].zome(ZOME_NAME),
+            "step_sm_path",
+            (
+                path_prefix.clone(),
+                "a".to_string(),
+                render_expr(&Expr::Prim(PrimOp::Nil)),
+            ),
+        )
+        .await;
+    assert!(is_sensemaker_error(&res, "SchemeMismatch"));
+
+    // no state was linked, & the next step is unaffected
+    let history: Vec<SmHistoryEntry> = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "get_sm_history_path",
+            (path_prefix.clone(), "a".to_string()),
+        )
+        .await;
+    assert_eq!(history.len(), 1);
+    let _: () = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "step_sm_path",
+            (path_prefix.clone(), "a".to_string(), int_source(1)),
+        )
+        .await;
+    let sm_data: Option<FlatValue<()>> = conductors[0]
+        .call(
+            &cells[0].zome(ZOME_NAME),
+            "get_sm_data_path",
+            (path_prefix, "a".to_string()),
+        )
+        .await;
+    assert_eq!(sm_data, Some(FlatValue(Value::VInt(1))));
+
+    Ok(())
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // helpers
 ////////////////////////////////////////////////////////////////////////////////
@@ -373,3 +2117,49 @@ fn nth_fib(mut n: i64) -> i64 {
 fn nth_sum_all(n: u32) -> i64 {
     2_i64.pow(n)
 }
+
+/// renders `expr` as rep_lang source, for the functions which parse their input.
+fn render_expr(expr: &rep_lang_core::abstract_syntax::Expr) -> String {
+    use rep_lang_concrete_syntax::{pretty::ppr_expr, util::pretty::to_pretty};
+
+    to_pretty(ppr_expr(expr), 80)
+}
+
+/// the source of an `Int` literal, e.g. a counter action.
+fn int_source(n: i64) -> String {
+    use rep_lang_core::abstract_syntax::{Expr, Lit};
+
+    render_expr(&Expr::Lit(Lit::LInt(n)))
+}
+
+/// a counter state machine, which adds each action to its state: `(sm_comp, sm_init)`,
+/// as rep_lang source.
+fn counter_sm() -> (String, String) {
+    use rep_lang_core::{
+        abstract_syntax::{Expr, Name, PrimOp},
+        app, lam,
+    };
+
+    let (n, d) = (Name("n".into()), Name("d".into()));
+    let sm_comp = lam!(
+        n.clone(),
+        lam!(
+            d.clone(),
+            app!(app!(Expr::Prim(PrimOp::Add), Expr::Var(n)), Expr::Var(d))
+        )
+    );
+    (render_expr(&sm_comp), int_source(0))
+}
+
+/// whether `res` failed with a `SensemakerError` of the given `kind`. the error reaches
+/// us inside the conductor's own, as JSON tagged by `kind` (which may be escaped).
+fn is_sensemaker_error<T, E: std::fmt::Debug>(res: &Result<T, E>, kind: &str) -> bool {
+    let kind_tag = format!("\"kind\":\"{}\"", kind);
+    match res {
+        Ok(_) => false,
+        Err(err) => {
+            let err_str = format!("{:?}", err);
+            err_str.contains(&kind_tag) || err_str.contains(&kind_tag.replace('"', "\\\""))
+        }
+    }
+}
@@ -1,5 +1,8 @@
 pub const OWNER_TAG: &str = "sensemaker_owner";
+pub const DEPENDENT_TAG: &str = "sensemaker_dependent";
+pub const SCHEME_BUCKET_TAG: &str = "sensemaker_scheme_bucket";
 pub const SENSEMAKER_ZOME_NAME: &str = "sensemaker_main";
 pub const SM_COMP_TAG: &str = "sm_comp";
 pub const SM_INIT_TAG: &str = "sm_init";
 pub const SM_DATA_TAG: &str = "sm_data";
+pub const SM_SNAPSHOT_TAG: &str = "sm_snapshot";